@@ -0,0 +1,106 @@
+use crate::config::Config;
+use crate::response::Response;
+use crate::{send_message_embed, Scope, Wrapper};
+
+use serenity::framework::standard::{
+    macros::{check, hook},
+    Args, CommandOptions, DispatchError, Reason,
+};
+use serenity::model::channel::Message;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+
+// Only lets users with the admin permission or the admin role of the server
+// manage its commands. Anyone may do so in a direct message.
+#[check]
+#[name = "Admin"]
+pub async fn admin_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    _: &CommandOptions,
+) -> Result<(), Reason> {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let roles = match &msg.member {
+        Some(member) => member.roles.clone(),
+        None => match guild_id.member(ctx, msg.author.id).await {
+            Ok(member) => member.roles,
+            Err(why) => return Err(Reason::Log(format!("Error getting member: {:?}", why))),
+        },
+    };
+
+    if is_admin(ctx, guild_id, msg.author.id, &roles).await {
+        Ok(())
+    } else {
+        Err(Reason::User(String::from("Not an admin")))
+    }
+}
+
+/// Tells the user why a command was refused.
+#[hook]
+pub async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError) {
+    match error {
+        DispatchError::CheckFailed(_, Reason::User(_)) => {
+            if let Err(why) = send_message_embed(denied(), msg.channel_id, &ctx.http).await {
+                println!("Error sending message: {:?}", why);
+            }
+        }
+        DispatchError::CheckFailed(_, Reason::Log(why)) => println!("{}", why),
+        _ => {}
+    }
+}
+
+/// Whether the user with `roles` may manage the commands of a server. That
+/// is the owner, anyone with the configured permission or the administrator
+/// permission, and anyone with the admin role of the server.
+pub async fn is_admin(ctx: &Context, guild_id: GuildId, user_id: UserId, roles: &[RoleId]) -> bool {
+    let data = ctx.data.read().await;
+    let required = data.get::<Config>().unwrap().admin_permission;
+    let admin_role = data
+        .get::<Wrapper>()
+        .unwrap()
+        .communities
+        .get(&Scope::Guild(guild_id))
+        .and_then(|community| community.admin_role);
+    drop(data);
+
+    if let Some(admin_role) = admin_role {
+        if roles.contains(&admin_role) {
+            return true;
+        }
+    }
+
+    let guild = match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(guild) => guild,
+        Err(why) => {
+            println!("Error getting server: {:?}", why);
+            return false;
+        }
+    };
+    if guild.owner_id == user_id {
+        return true;
+    }
+
+    // @everyone shares its id with the server
+    let everyone = RoleId(guild_id.0);
+    let permissions = guild
+        .roles
+        .values()
+        .filter(|role| role.id == everyone || roles.contains(&role.id))
+        .fold(Permissions::empty(), |permissions, role| {
+            permissions | role.permissions
+        });
+    permissions.contains(Permissions::ADMINISTRATOR) || permissions.contains(required)
+}
+
+/// The reply to a user that may not manage the commands of the server.
+pub fn denied() -> Response {
+    let title = String::from("Not allowed");
+    let text =
+        String::from("Only server admins and members with the admin role can manage the commands.");
+    Response::new_error(title, text)
+}
@@ -1,11 +1,21 @@
 use chrono::prelude::*;
-use reqwest::{self, IntoUrl};
+use reqwest::{self, header::RETRY_AFTER, Client, StatusCode};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
 
 use crate::bout::Bout;
+use crate::config::Config;
+use crate::outcome::{Outcome, Side};
 use crate::response::Response;
+use crate::scouting::{Scouting, RECENT_BOUTS};
+use crate::standings::Standings;
 
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct ApiBoutResult {
     code: String,
@@ -16,128 +26,1242 @@ struct ApiBoutResult {
 struct JBout {
     id: usize,
     datetime: String,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    winner: Option<char>,
     maps: Vec<JMap>,
     tournament: JTournament,
+    #[serde(default)]
+    stage: Option<JStage>,
     lineups: HashMap<char, JTeam>,
 }
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct JMap {
     id: usize,
     name: String,
+    #[serde(default)]
+    winner: Option<char>,
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct JTournament {
     id: usize,
     name: String,
 }
 
+/// The group or bracket of a tournament a match is played in.
+#[derive(Deserialize, Debug)]
+struct JStage {
+    id: usize,
+    name: String,
+}
+
 #[derive(Deserialize, Debug)]
 struct JTeam {
     id: usize,
     name: String,
+    #[serde(default)]
+    score: Option<usize>,
+    #[serde(default)]
+    players: Vec<JPlayer>,
+}
+
+#[derive(Deserialize, Debug)]
+struct JPlayer {
+    name: String,
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct ApiTournamentResult {
     code: String,
     result: JContent,
 }
 
+/// A page of matches. The paging fields are optional, a response without
+/// them is treated as the only page.
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 struct JContent {
     content: Vec<JBout>,
+    #[serde(default)]
+    number: Option<usize>,
+    #[serde(default)]
+    total_pages: Option<usize>,
+    #[serde(default)]
+    last: Option<bool>,
 }
 
-fn parse_tournament_data(data: &str) -> serde_json::Result<ApiTournamentResult> {
-    let parsed: ApiTournamentResult = serde_json::from_str(&data)?;
-    Ok(parsed)
+impl JContent {
+    /// Whether more pages follow this one.
+    fn has_next(&self) -> bool {
+        if self.content.is_empty() {
+            return false;
+        }
+
+        match (self.last, self.number, self.total_pages) {
+            (Some(last), _, _) => !last,
+            (None, Some(number), Some(total_pages)) => number + 1 < total_pages,
+            _ => false,
+        }
+    }
 }
 
-/// Attempt to parse raw JSON bout data to a `ApiBoutResult`.
-fn parse_bout_data(data: &str) -> serde_json::Result<ApiBoutResult> {
-    let parsed: ApiBoutResult = serde_json::from_str(&data)?;
-    Ok(parsed)
+/// Everything that can go wrong while talking to the spire.gg API.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request could not be sent or the response could not be read.
+    Network { url: String, source: reqwest::Error },
+
+    /// spire.gg answered with an unsuccessful status code.
+    Status { url: String, status: StatusCode },
+
+    /// The response was not the expected JSON.
+    Decode {
+        url: String,
+        source: serde_json::Error,
+    },
+
+    /// A match does not list the team on the given side (`'A'` or `'B'`).
+    MissingLineup { bout_id: usize, side: char },
+
+    /// The date and time of a match could not be parsed.
+    BadDatetime { bout_id: usize, datetime: String },
+
+    /// The team has no matches in the tournament.
+    NoMatches {
+        tournament_id: usize,
+        team_id: usize,
+    },
+
+    /// The match has not been played yet, so it has no result.
+    Unfinished { bout_id: usize },
 }
 
-/// Posts a GET-request to a specific URL.
-async fn make_request<T: IntoUrl>(request: T) -> Result<String, reqwest::Error> {
-    let result = reqwest::get(request).await?.text().await?;
-    Ok(result)
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network { url, source } => write!(f, "request to {} failed: {}", url, source),
+            ApiError::Status { url, status } => write!(f, "{} answered with {}", url, status),
+            ApiError::Decode { url, source } => {
+                write!(f, "could not parse the response of {}: {}", url, source)
+            }
+            ApiError::MissingLineup { bout_id, side } => {
+                write!(f, "match {} has no team on side {}", bout_id, side)
+            }
+            ApiError::BadDatetime { bout_id, datetime } => {
+                write!(f, "match {} has an invalid date \"{}\"", bout_id, datetime)
+            }
+            ApiError::NoMatches {
+                tournament_id,
+                team_id,
+            } => write!(
+                f,
+                "team {} has no matches in tournament {}",
+                team_id, tournament_id
+            ),
+            ApiError::Unfinished { bout_id } => write!(f, "match {} is not finished", bout_id),
+        }
+    }
 }
 
-/// Gets the match (referred to as Bout to avoid overlap with the Rust keyword
-/// `match`) with `bout_id` from the spire.gg API.
-async fn get_bout(bout_id: usize) -> Result<Bout, Response> {
-    let address = format!("https://api.spire.gg/matches/{}", bout_id);
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Network { source, .. } => Some(source),
+            ApiError::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
-    match make_request(&address).await {
-        Ok(data) => match parse_bout_data(&data) {
-            Ok(parsed) => {
-                let tournament_name = parsed.result.tournament.name;
-                let mut raw_dt = parsed.result.datetime.clone();
-                raw_dt.push('Z');
+impl ApiError {
+    /// Converts the error into a message for Discord. `site_url` is the base
+    /// URL of the spire.gg website, used to link to the tournament.
+    pub fn to_response(&self, site_url: &str) -> Response {
+        match self {
+            ApiError::NoMatches { tournament_id, .. } => {
+                let title = String::from("No active matches found");
+                let message = format!(
+                    "For further information see {}/tournament/{}#brackets.",
+                    site_url, tournament_id
+                );
+                Response::new_error(title, message)
+            }
+            ApiError::Unfinished { bout_id } => {
+                let title = String::from("No result yet");
+                let message = format!(
+                    "The match has not been played yet, see {}/match/{}.",
+                    site_url, bout_id
+                );
+                Response::new_warning(title, message)
+            }
+            _ => {
+                let title = String::from("API error");
+                let message = format!("Error retrieving data from spire.gg!\n\t{}", self);
+                Response::new_error(title, message)
+            }
+        }
+    }
+}
 
-                let datetime = raw_dt.parse::<DateTime<Utc>>().unwrap();
-                let maps = parsed
-                    .result
-                    .maps
-                    .into_iter()
-                    .map(|jmap| jmap.name)
-                    .collect();
+/// Attempt to parse raw JSON tournament data, as requested from `url`.
+fn parse_tournament_data(url: &str, data: &str) -> Result<ApiTournamentResult, ApiError> {
+    serde_json::from_str(data).map_err(|source| ApiError::Decode {
+        url: String::from(url),
+        source,
+    })
+}
 
-                let home = parsed.result.lineups.get(&'A').unwrap().name.clone();
-                let away = parsed.result.lineups.get(&'B').unwrap().name.clone();
+/// Attempt to parse raw JSON bout data, as requested from `url`, to a
+/// `ApiBoutResult`.
+fn parse_bout_data(url: &str, data: &str) -> Result<ApiBoutResult, ApiError> {
+    serde_json::from_str(data).map_err(|source| ApiError::Decode {
+        url: String::from(url),
+        source,
+    })
+}
 
-                let bout = Bout::new(bout_id, tournament_name, datetime, maps, home, away);
-                Ok(bout)
+/// Parses the date and time of a match. spire.gg omits the timezone, but
+/// the times are in UTC.
+fn parse_datetime(bout_id: usize, raw: &str) -> Result<DateTime<Utc>, ApiError> {
+    format!("{}Z", raw)
+        .parse::<DateTime<Utc>>()
+        .map_err(|_| ApiError::BadDatetime {
+            bout_id,
+            datetime: String::from(raw),
+        })
+}
+
+/// Gets the name of the team on `side` of a match.
+fn team_name(
+    bout_id: usize,
+    lineups: &HashMap<char, JTeam>,
+    side: char,
+) -> Result<String, ApiError> {
+    match lineups.get(&side) {
+        Some(team) => Ok(team.name.clone()),
+        None => Err(ApiError::MissingLineup { bout_id, side }),
+    }
+}
+
+/// Whether the team with `team_id` plays in a match.
+fn plays_in(jbout: &JBout, team_id: usize) -> bool {
+    jbout.lineups.values().any(|team| team.id == team_id)
+}
+
+/// Gets the side on which the team with `team_id` plays in a match.
+fn side_of(jbout: &JBout, team_id: usize) -> Option<Side> {
+    jbout
+        .lineups
+        .iter()
+        .find(|(_, team)| team.id == team_id)
+        .and_then(|(side, _)| to_side(*side))
+}
+
+/// Whether spire.gg marks a match as played.
+fn is_finished(jbout: &JBout) -> bool {
+    match &jbout.status {
+        Some(status) => status.eq_ignore_ascii_case("finished"),
+        None => false,
+    }
+}
+
+/// Gets the remaining matches of the team with `team_id` in chronological
+/// order: the matches that aren't finished and started at most `grace`
+/// before `now`, so a match in progress still counts as remaining. Matches
+/// with an invalid date are skipped.
+fn upcoming_bouts(
+    bouts: &[JBout],
+    team_id: usize,
+    now: DateTime<Utc>,
+    grace: Duration,
+) -> Vec<&JBout> {
+    let grace = chrono::Duration::from_std(grace).unwrap_or_else(|_| chrono::Duration::zero());
+
+    let mut upcoming: Vec<_> = bouts
+        .iter()
+        .filter(|jbout| plays_in(jbout, team_id) && !is_finished(jbout))
+        .filter_map(|jbout| match parse_datetime(jbout.id, &jbout.datetime) {
+            Ok(datetime) => Some((datetime, jbout)),
+            Err(why) => {
+                println!("Skipping match: {}", why);
+                None
             }
-            Err(why) => Err(create_api_error_response(why.to_string(), address)),
-        },
-        Err(why) => Err(create_api_error_response(why.to_string(), address)),
-    }
-}
-
-pub async fn find_next_bout(tournament_id: usize, team_id: usize) -> Result<Bout, Response> {
-    let address = format!(
-        "https://api.spire.gg/matches?tournamentId={}",
-        tournament_id
-    );
-    match make_request(&address).await {
-        Ok(data) => match parse_tournament_data(&data) {
-            Ok(parsed) => {
-                let team_bouts: Vec<_> = parsed
-                    .result
-                    .content
-                    .iter()
-                    .filter(|jbout| {
-                        jbout.lineups.get(&'A').unwrap().id == team_id
-                            || jbout.lineups.get(&'B').unwrap().id == team_id
-                    })
-                    .collect();
-
-                if team_bouts.len() == 0 {
-                    let title = String::from("No active matches found");
-                    let message = format!(
-                        "For further information see https://spire.gg/tournament/{}#brackets.",
-                        tournament_id
-                    );
-                    let response = Response::new_error(title, message);
-                    return Err(response);
+        })
+        .filter(|(datetime, _)| *datetime + grace >= now)
+        .collect();
+
+    upcoming.sort_by_key(|(datetime, _)| *datetime);
+    upcoming.into_iter().map(|(_, jbout)| jbout).collect()
+}
+
+/// Picks the next match of the team with `team_id`, see `upcoming_bouts`.
+fn select_next_bout(
+    bouts: &[JBout],
+    team_id: usize,
+    now: DateTime<Utc>,
+    grace: Duration,
+) -> Option<&JBout> {
+    upcoming_bouts(bouts, team_id, now, grace)
+        .into_iter()
+        .next()
+}
+
+/// Gets the finished matches of the team with `team_id`, latest first.
+/// Matches with an invalid date are skipped.
+fn finished_bouts(bouts: &[JBout], team_id: usize) -> Vec<&JBout> {
+    let mut finished: Vec<_> = bouts
+        .iter()
+        .filter(|jbout| plays_in(jbout, team_id) && is_finished(jbout))
+        .filter_map(|jbout| match parse_datetime(jbout.id, &jbout.datetime) {
+            Ok(datetime) => Some((datetime, jbout)),
+            Err(_) => None,
+        })
+        .collect();
+
+    finished.sort_by_key(|(datetime, _)| std::cmp::Reverse(*datetime));
+    finished.into_iter().map(|(_, jbout)| jbout).collect()
+}
+
+/// Picks the latest finished match of the team with `team_id`.
+fn select_last_bout(bouts: &[JBout], team_id: usize) -> Option<&JBout> {
+    finished_bouts(bouts, team_id).into_iter().next()
+}
+
+/// Converts a side of a match (`'A'` or `'B'`) into a `Side`.
+fn to_side(side: char) -> Option<Side> {
+    match side {
+        'A' => Some(Side::Home),
+        'B' => Some(Side::Away),
+        _ => None,
+    }
+}
+
+/// Converts a finished match of the API into its `Outcome`.
+fn to_outcome(jbout: &JBout) -> Result<Outcome, ApiError> {
+    if !is_finished(jbout) {
+        return Err(ApiError::Unfinished { bout_id: jbout.id });
+    }
+
+    let home = team_name(jbout.id, &jbout.lineups, 'A')?;
+    let away = team_name(jbout.id, &jbout.lineups, 'B')?;
+    let score = |side| jbout.lineups.get(&side).and_then(|team| team.score);
+    let score = match (score('A'), score('B')) {
+        (Some(home), Some(away)) => Some((home, away)),
+        _ => None,
+    };
+    let maps = jbout
+        .maps
+        .iter()
+        .map(|jmap| (jmap.name.clone(), jmap.winner.and_then(to_side)))
+        .collect();
+
+    let winner = jbout.winner.and_then(to_side);
+
+    Ok(Outcome::new(jbout.id, home, away, winner, score, maps))
+}
+
+/// Gets the id of the team on `side` of a match.
+fn team_id(bout_id: usize, lineups: &HashMap<char, JTeam>, side: char) -> Result<usize, ApiError> {
+    match lineups.get(&side) {
+        Some(team) => Ok(team.id),
+        None => Err(ApiError::MissingLineup { bout_id, side }),
+    }
+}
+
+/// Gets the group or bracket of the latest match of the team with
+/// `team_id`, the one it currently plays in or was last ranked in. Matches
+/// with an invalid date are skipped.
+fn current_stage(bouts: &[JBout], team_id: usize) -> Option<&JStage> {
+    bouts
+        .iter()
+        .filter(|jbout| plays_in(jbout, team_id))
+        .filter_map(|jbout| match parse_datetime(jbout.id, &jbout.datetime) {
+            Ok(datetime) => Some((datetime, jbout)),
+            Err(_) => None,
+        })
+        .max_by_key(|(datetime, _)| *datetime)
+        .and_then(|(_, jbout)| jbout.stage.as_ref())
+}
+
+/// Computes the standings of the team with `team_id` from the results of
+/// the finished matches of its group or bracket, see `current_stage`. A
+/// tournament without stages is ranked as a whole. Results of matches
+/// without both teams are skipped.
+fn to_standings(bouts: &[JBout], team_id: usize) -> Standings {
+    let tournament = match bouts.first() {
+        Some(jbout) => jbout.tournament.name.clone(),
+        None => String::new(),
+    };
+    let stage = current_stage(bouts, team_id);
+    let stage_id = stage.map(|stage| stage.id);
+    let mut standings = Standings::new(tournament, stage.map(|stage| stage.name.clone()), team_id);
+
+    for jbout in bouts {
+        if jbout.stage.as_ref().map(|stage| stage.id) != stage_id {
+            continue;
+        }
+        for team in jbout.lineups.values() {
+            standings.add_team(team.id, &team.name);
+        }
+        if !is_finished(jbout) {
+            continue;
+        }
+
+        if let Err(why) = record_result(&mut standings, jbout) {
+            println!("Skipping result: {}", why);
+        }
+    }
+    standings
+}
+
+/// Records the result of a finished match in the standings.
+fn record_result(standings: &mut Standings, jbout: &JBout) -> Result<(), ApiError> {
+    let home = team_id(jbout.id, &jbout.lineups, 'A')?;
+    let away = team_id(jbout.id, &jbout.lineups, 'B')?;
+    let outcome = to_outcome(jbout)?;
+    standings.record(home, away, &outcome);
+    Ok(())
+}
+
+/// Converts a match of the API into a `Bout` without any players.
+fn to_bout(jbout: &JBout) -> Result<Bout, ApiError> {
+    let datetime = parse_datetime(jbout.id, &jbout.datetime)?;
+    let home = team_name(jbout.id, &jbout.lineups, 'A')?;
+    let away = team_name(jbout.id, &jbout.lineups, 'B')?;
+    let maps = jbout.maps.iter().map(|jmap| jmap.name.clone()).collect();
+    let tournament = jbout.tournament.name.clone();
+
+    Ok(Bout::new(jbout.id, tournament, datetime, maps, home, away))
+}
+
+/// Maximum number of pages requested of a tournament, guarding against an
+/// API that keeps reporting more pages.
+const MAX_PAGES: usize = 50;
+
+/// Longest `Retry-After` the client is willing to wait for. Requests are made
+/// while handling a message, so waiting any longer blocks the bot.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Reads the delay requested by the `Retry-After` header of a response, given
+/// either in seconds or as a date.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let remaining = date.signed_duration_since(Utc::now()).to_std();
+    Some(remaining.unwrap_or(Duration::from_secs(0)))
+}
+
+/// Responses of spire.gg by URL, reused until they expire.
+struct Cache {
+    /// Time for which a response is reused. Zero disables the cache.
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+impl Cache {
+    fn new(ttl: Duration) -> Cache {
+        Cache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Gets the response for `url`, if it hasn't expired yet.
+    fn get(&self, url: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(url) {
+            Some((stored, data)) if stored.elapsed() < self.ttl => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    /// Stores the response for `url`, dropping all expired responses.
+    fn insert(&self, url: &str, data: String) {
+        if self.ttl.as_nanos() == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        let ttl = self.ttl;
+        entries.retain(|_, (stored, _)| stored.elapsed() < ttl);
+        entries.insert(String::from(url), (Instant::now(), data));
+    }
+
+    /// Drops all stored responses, returning how many there were.
+    fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+/// Client for the spire.gg API.
+pub struct Spire {
+    /// Base URL of the API, e.g. `https://api.spire.gg`.
+    api_url: String,
+
+    /// Base URL of the website, e.g. `https://spire.gg`.
+    site_url: String,
+
+    /// HTTP client shared by all requests, configured with the timeout.
+    client: Client,
+
+    /// Number of times a failed request is retried.
+    retries: u32,
+
+    /// Delay before the first retry, doubled for every next retry.
+    backoff: Duration,
+
+    /// Recent responses, so bursts of sign-ups don't all hit spire.gg.
+    cache: Cache,
+
+    /// Time after its start during which a match is still the next match.
+    grace: Duration,
+}
+
+impl Spire {
+    /// Constructs a new client from the configuration. Trailing slashes of
+    /// the URLs are ignored.
+    pub fn new(config: &Config) -> Spire {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .expect("Err creating HTTP client");
+
+        Spire {
+            api_url: String::from(config.api_url.trim_end_matches('/')),
+            site_url: String::from(config.site_url.trim_end_matches('/')),
+            client,
+            retries: config.retries,
+            backoff: config.backoff,
+            cache: Cache::new(config.cache_ttl),
+            grace: config.grace,
+        }
+    }
+
+    /// Drops all cached responses, so the next lookups get fresh data.
+    /// Returns the number of dropped responses.
+    pub fn clear_cache(&self) -> usize {
+        self.cache.clear()
+    }
+
+    /// Gets the response for `url`, from the cache if it is recent enough.
+    async fn fetch(&self, url: &str) -> Result<String, ApiError> {
+        if let Some(data) = self.cache.get(url) {
+            return Ok(data);
+        }
+
+        let data = self.make_request(url).await?;
+        self.cache.insert(url, data.clone());
+        Ok(data)
+    }
+
+    /// Posts a GET-request to a specific URL. Connection errors and server
+    /// errors are retried with exponential backoff, and rate limits are
+    /// retried after the delay spire.gg asks for. Timeouts are not retried.
+    async fn make_request(&self, url: &str) -> Result<String, ApiError> {
+        let network = |source| ApiError::Network {
+            url: String::from(url),
+            source,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let backoff = self.backoff * 2u32.saturating_pow(attempt);
+            let retry = attempt < self.retries;
+            attempt += 1;
+
+            let delay = match self.client.get(url).send().await {
+                Ok(response) if response.status().is_success() => {
+                    return response.text().await.map_err(network);
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let delay = if status == StatusCode::TOO_MANY_REQUESTS {
+                        retry_after(&response).or(Some(backoff))
+                    } else if status.is_server_error() {
+                        Some(backoff)
+                    } else {
+                        None
+                    };
+
+                    match delay {
+                        Some(delay) if retry && delay <= MAX_RETRY_AFTER => delay,
+                        _ => {
+                            let url = String::from(url);
+                            return Err(ApiError::Status { url, status });
+                        }
+                    }
                 }
+                Err(source) if retry && source.is_connect() && !source.is_timeout() => backoff,
+                Err(source) => return Err(network(source)),
+            };
+
+            sleep(delay).await;
+        }
+    }
+
+    /// Base URL of the spire.gg website, used for links to matches and
+    /// tournaments.
+    pub fn site_url(&self) -> &str {
+        &self.site_url
+    }
+
+    /// Converts an error of this client into a message for Discord.
+    pub fn error_response(&self, why: &ApiError) -> Response {
+        why.to_response(&self.site_url)
+    }
+
+    /// Gets the match (referred to as Bout to avoid overlap with the Rust
+    /// keyword `match`) with `bout_id` from the spire.gg API.
+    async fn get_bout(&self, bout_id: usize) -> Result<Bout, ApiError> {
+        to_bout(&self.get_match(bout_id).await?)
+    }
+
+    /// Gets the details of the match with `bout_id`.
+    async fn get_match(&self, bout_id: usize) -> Result<JBout, ApiError> {
+        let address = format!("{}/matches/{}", self.api_url, bout_id);
+        let data = self.fetch(&address).await?;
+        Ok(parse_bout_data(&address, &data)?.result)
+    }
+
+    /// Gets the matches of a tournament, walking all pages of the listing.
+    async fn get_tournament_bouts(&self, tournament_id: usize) -> Result<Vec<JBout>, ApiError> {
+        let mut bouts = Vec::new();
+        for page in 0..MAX_PAGES {
+            let address = format!(
+                "{}/matches?tournamentId={}&page={}",
+                self.api_url, tournament_id, page
+            );
+            let data = self.fetch(&address).await?;
+            let mut parsed = parse_tournament_data(&address, &data)?.result;
+
+            let has_next = parsed.has_next();
+            bouts.append(&mut parsed.content);
+            if !has_next {
+                break;
+            }
+        }
+        Ok(bouts)
+    }
+
+    /// Gets the result of the match with `bout_id`.
+    pub async fn get_outcome(&self, bout_id: usize) -> Result<Outcome, ApiError> {
+        to_outcome(&self.get_match(bout_id).await?)
+    }
+
+    /// Scouts the opponent of the team with `team_id` in the match with
+    /// `bout_id`, or in the next match if it isn't listed: the results of
+    /// its recent finished matches in the tournament, its win rate per map
+    /// and its players.
+    pub async fn scout_opponent(
+        &self,
+        tournament_id: usize,
+        team_id: usize,
+        bout_id: Option<usize>,
+    ) -> Result<Scouting, ApiError> {
+        let bouts = self.get_tournament_bouts(tournament_id).await?;
+        let tracked = bouts
+            .iter()
+            .find(|jbout| Some(jbout.id) == bout_id && plays_in(jbout, team_id));
+        let opponent = tracked
+            .or_else(|| select_next_bout(&bouts, team_id, Utc::now(), self.grace))
+            .and_then(|jbout| jbout.lineups.values().find(|team| team.id != team_id));
+        let opponent = match opponent {
+            Some(opponent) => opponent,
+            None => {
+                return Err(ApiError::NoMatches {
+                    tournament_id,
+                    team_id,
+                })
+            }
+        };
 
-                let bout_id = team_bouts[0].id;
-                get_bout(bout_id).await
+        let mut scouting = Scouting::new(opponent.name.clone());
+        for jbout in finished_bouts(&bouts, opponent.id)
+            .into_iter()
+            .take(RECENT_BOUTS)
+        {
+            let details = self.get_match(jbout.id).await?;
+            let side = match side_of(&details, opponent.id) {
+                Some(side) => side,
+                None => continue,
+            };
+            scouting.add_bout(&to_outcome(&details)?, side);
+            for team in details
+                .lineups
+                .values()
+                .filter(|team| team.id == opponent.id)
+            {
+                for player in &team.players {
+                    scouting.add_player(&player.name);
+                }
             }
+        }
+        Ok(scouting)
+    }
+
+    /// Gets the result of the latest finished match of the team with
+    /// `team_id` in a tournament.
+    pub async fn find_last_outcome(
+        &self,
+        tournament_id: usize,
+        team_id: usize,
+    ) -> Result<Outcome, ApiError> {
+        let bouts = self.get_tournament_bouts(tournament_id).await?;
+        match select_last_bout(&bouts, team_id) {
+            Some(jbout) => self.get_outcome(jbout.id).await,
+            None => Err(ApiError::NoMatches {
+                tournament_id,
+                team_id,
+            }),
+        }
+    }
+
+    /// Gets the standings of a tournament, highlighting the team with
+    /// `team_id`.
+    pub async fn get_standings(
+        &self,
+        tournament_id: usize,
+        team_id: usize,
+    ) -> Result<Standings, ApiError> {
+        let bouts = self.get_tournament_bouts(tournament_id).await?;
+        if !bouts.iter().any(|jbout| plays_in(jbout, team_id)) {
+            return Err(ApiError::NoMatches {
+                tournament_id,
+                team_id,
+            });
+        }
 
-            Err(why) => Err(create_api_error_response(why.to_string(), address)),
-        },
-        Err(why) => Err(create_api_error_response(why.to_string(), address)),
+        Ok(to_standings(&bouts, team_id))
+    }
+
+    /// Gets the remaining matches of the team with `team_id` in a
+    /// tournament, in chronological order.
+    pub async fn get_schedule(
+        &self,
+        tournament_id: usize,
+        team_id: usize,
+    ) -> Result<Vec<Bout>, ApiError> {
+        let bouts = self.get_tournament_bouts(tournament_id).await?;
+        let upcoming = upcoming_bouts(&bouts, team_id, Utc::now(), self.grace);
+        if upcoming.is_empty() {
+            return Err(ApiError::NoMatches {
+                tournament_id,
+                team_id,
+            });
+        }
+
+        upcoming.into_iter().map(to_bout).collect()
+    }
+
+    pub async fn find_next_bout(
+        &self,
+        tournament_id: usize,
+        team_id: usize,
+    ) -> Result<Bout, ApiError> {
+        let bouts = self.get_tournament_bouts(tournament_id).await?;
+        match select_next_bout(&bouts, team_id, Utc::now(), self.grace) {
+            Some(jbout) => self.get_bout(jbout.id).await,
+            None => Err(ApiError::NoMatches {
+                tournament_id,
+                team_id,
+            }),
+        }
     }
 }
 
-fn create_api_error_response(why: String, address: String) -> Response {
-    let title = String::from("API error");
-    let message = format!("Error parsing response of \"{}\"!\n\t{}", address, why);
-    Response::new_error(title, message)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{edit_fixture, read_fixture, MockResponse, MockSpire};
+
+    #[tokio::test]
+    async fn finds_the_bout_of_the_team() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+
+        let spire = Spire::new(&server.config());
+        let bout = spire.find_next_bout(42, 202).await.unwrap();
+
+        assert_eq!(bout.get_title(), "Beserkers vs Team Liquid Nitrogen");
+        assert!(bout.get_maps().starts_with("[1]: Death Aura LE\n"));
+        assert_eq!(
+            server.requests(),
+            vec!["/matches?tournamentId=42&page=0", "/matches/5001"]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_teams_without_matches() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+
+        let config = Config {
+            site_url: String::from("https://spire.test/"),
+            ..server.config()
+        };
+        let spire = Spire::new(&config);
+        let why = spire.find_next_bout(42, 999).await.unwrap_err();
+        assert!(matches!(
+            why,
+            ApiError::NoMatches {
+                tournament_id: 42,
+                team_id: 999
+            }
+        ));
+
+        let response = spire.error_response(&why);
+        assert_eq!(response.title, "No active matches found");
+        assert!(response
+            .contents
+            .contains("https://spire.test/tournament/42#brackets"));
+    }
+
+    #[tokio::test]
+    async fn reports_unsuccessful_status_codes() {
+        let server = MockSpire::start().await;
+
+        let spire = Spire::new(&server.config());
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+
+        assert!(matches!(why, ApiError::Status { status, .. } if status == 404));
+        assert_eq!(spire.error_response(&why).title, "API error");
+    }
+
+    #[tokio::test]
+    async fn reports_malformed_matches() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        let spire = Spire::new(&server.config());
+
+        server.route("/matches/5001", 200, "<html>maintenance</html>");
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+        assert!(matches!(why, ApiError::Decode { .. }));
+
+        let match_5001 = read_fixture("match_5001.json");
+        let bad_date = match_5001.replace("2099-03-20T19:00:00", "next tuesday");
+        server.route("/matches/5001", 200, &bad_date);
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+        assert!(matches!(why, ApiError::BadDatetime { bout_id: 5001, .. }));
+
+        let bye = match_5001.replace("\"B\":", "\"C\":");
+        server.route("/matches/5001", 200, &bye);
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+        assert!(matches!(
+            why,
+            ApiError::MissingLineup {
+                bout_id: 5001,
+                side: 'B'
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+        server.respond_once("/matches/5001", MockResponse::new(502, "{}"));
+        server.respond_once("/matches/5001", MockResponse::new(503, "{}"));
+
+        let spire = Spire::new(&server.config());
+        assert!(spire.find_next_bout(42, 101).await.is_ok());
+        assert_eq!(server.hits("/matches/5001"), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_retry() {
+        let server = MockSpire::start().await;
+        server.route("/matches?tournamentId=42&page=0", 500, "{}");
+
+        let config = Config {
+            retries: 2,
+            ..server.config()
+        };
+        let spire = Spire::new(&config);
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+
+        assert!(matches!(why, ApiError::Status { status, .. } if status == 500));
+        assert_eq!(server.hits("/matches?tournamentId=42&page=0"), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = MockSpire::start().await;
+
+        let spire = Spire::new(&server.config());
+        assert!(spire.find_next_bout(42, 101).await.is_err());
+        assert_eq!(server.hits("/matches?tournamentId=42&page=0"), 1);
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+        let limited = MockResponse::new(429, "{}").header("Retry-After", "1");
+        server.respond_once("/matches?tournamentId=42&page=0", limited);
+
+        let spire = Spire::new(&server.config());
+        let start = Instant::now();
+        assert!(spire.find_next_bout(42, 101).await.is_ok());
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_long_retry_after() {
+        let server = MockSpire::start().await;
+        let limited = MockResponse::new(429, "{}").header("Retry-After", "3600");
+        server.respond("/matches?tournamentId=42&page=0", limited);
+
+        let spire = Spire::new(&server.config());
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+
+        assert!(matches!(why, ApiError::Status { status, .. } if status == 429));
+        assert_eq!(server.hits("/matches?tournamentId=42&page=0"), 1);
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_responses() {
+        let server = MockSpire::start().await;
+        let slow = MockResponse::new(200, "{}").delay(Duration::from_secs(5));
+        server.respond("/matches?tournamentId=42&page=0", slow);
+
+        let config = Config {
+            timeout: Duration::from_millis(200),
+            ..server.config()
+        };
+        let spire = Spire::new(&config);
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+
+        assert!(matches!(why, ApiError::Network { ref source, .. } if source.is_timeout()));
+        assert_eq!(server.hits("/matches?tournamentId=42&page=0"), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_connection_errors() {
+        // reserve a port that nothing listens on
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let config = Config {
+            api_url: format!("http://{}", address),
+            backoff: Duration::from_millis(50),
+            retries: 2,
+            ..Config::default()
+        };
+        let spire = Spire::new(&config);
+        let start = Instant::now();
+        let why = spire.find_next_bout(42, 101).await.unwrap_err();
+
+        assert!(matches!(why, ApiError::Network { ref source, .. } if source.is_connect()));
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn reuses_recent_responses() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+
+        let config = Config {
+            cache_ttl: Duration::from_secs(60),
+            ..server.config()
+        };
+        let spire = Spire::new(&config);
+        for _ in 0..3 {
+            spire.find_next_bout(42, 101).await.unwrap();
+        }
+        assert_eq!(server.requests().len(), 2);
+
+        assert_eq!(spire.clear_cache(), 2);
+        spire.find_next_bout(42, 101).await.unwrap();
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn refetches_expired_responses() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+
+        let config = Config {
+            cache_ttl: Duration::from_millis(100),
+            ..server.config()
+        };
+        let spire = Spire::new(&config);
+        spire.find_next_bout(42, 101).await.unwrap();
+        sleep(Duration::from_millis(150)).await;
+        spire.find_next_bout(42, 101).await.unwrap();
+
+        assert_eq!(server.hits("/matches/5001"), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_errors() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+        server.respond_once(
+            "/matches?tournamentId=42&page=0",
+            MockResponse::new(404, "{}"),
+        );
+
+        let config = Config {
+            cache_ttl: Duration::from_secs(60),
+            ..server.config()
+        };
+        let spire = Spire::new(&config);
+        assert!(spire.find_next_bout(42, 101).await.is_err());
+        assert!(spire.find_next_bout(42, 101).await.is_ok());
+    }
+
+    /// Parses the matches of the recorded tournament, with the match of
+    /// `bout_id` moved to `datetime`.
+    fn tournament_with(bout_id: usize, datetime: DateTime<Utc>) -> Vec<JBout> {
+        let data = read_fixture("tournament_42.json");
+        let mut bouts = parse_tournament_data("", &data).unwrap().result.content;
+        for jbout in bouts.iter_mut().filter(|jbout| jbout.id == bout_id) {
+            jbout.datetime = datetime.format("%Y-%m-%dT%H:%M:%S").to_string();
+        }
+        bouts
+    }
+
+    #[tokio::test]
+    async fn skips_finished_and_later_matches() {
+        let server = MockSpire::start().await;
+        // the finished 5000 and the later 5002 are listed before 5001
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5000", "match_5000.json");
+        server.fixture("/matches/5001", "match_5001.json");
+        let later = edit_fixture("match_5001.json", |json| {
+            json["result"]["id"] = 5002.into();
+            json["result"]["datetime"] = "2099-03-27T19:00:00".into();
+        });
+        server.route("/matches/5002", 200, &later);
+
+        let spire = Spire::new(&server.config());
+        let bout = spire.find_next_bout(42, 101).await.unwrap();
+
+        assert_eq!(bout.get_id(), 5001);
+        assert_eq!(server.hits("/matches/5000"), 0);
+        assert_eq!(server.hits("/matches/5002"), 0);
+    }
+
+    #[test]
+    fn keeps_matches_in_progress_within_the_grace_window() {
+        let now = Utc::now();
+        let grace = Duration::from_secs(2 * 60 * 60);
+
+        let started = tournament_with(5001, now - chrono::Duration::minutes(90));
+        let next = select_next_bout(&started, 101, now, grace).unwrap();
+        assert_eq!(next.id, 5001);
+
+        let played = tournament_with(5001, now - chrono::Duration::minutes(150));
+        let next = select_next_bout(&played, 101, now, grace).unwrap();
+        assert_eq!(next.id, 5002);
+    }
+
+    #[test]
+    fn finds_nothing_when_all_matches_are_played() {
+        let data = read_fixture("tournament_42.json");
+        let bouts = parse_tournament_data("", &data).unwrap().result.content;
+        let now = Utc.ymd(2100, 1, 1).and_hms(0, 0, 0);
+
+        assert!(select_next_bout(&bouts, 101, now, Duration::from_secs(0)).is_none());
+    }
+
+    #[tokio::test]
+    async fn walks_all_pages_of_the_tournament() {
+        // spread the matches over two pages, with the team only on the last
+        let page = |number: usize, ids: &[u64]| {
+            edit_fixture("tournament_42.json", |json| {
+                let result = &mut json["result"];
+                let content = result["content"].as_array_mut().unwrap();
+                content.retain(|jbout| ids.contains(&jbout["id"].as_u64().unwrap()));
+                result["number"] = number.into();
+                result["size"] = 2.into();
+                result["totalPages"] = 2.into();
+                result["last"] = (number == 1).into();
+            })
+        };
+
+        let server = MockSpire::start().await;
+        server.route(
+            "/matches?tournamentId=42&page=0",
+            200,
+            &page(0, &[5000, 5003]),
+        );
+        server.route(
+            "/matches?tournamentId=42&page=1",
+            200,
+            &page(1, &[5001, 5002]),
+        );
+        server.fixture("/matches/5001", "match_5001.json");
+
+        let spire = Spire::new(&server.config());
+        let bout = spire.find_next_bout(42, 101).await.unwrap();
+
+        assert_eq!(bout.get_title(), "Beserkers vs Team Liquid Nitrogen");
+        assert_eq!(server.hits("/matches?tournamentId=42&page=2"), 0);
+    }
+
+    #[test]
+    fn treats_responses_without_paging_as_one_page() {
+        let content = JContent {
+            content: Vec::new(),
+            number: None,
+            total_pages: None,
+            last: None,
+        };
+        assert!(!content.has_next());
+
+        let data = read_fixture("tournament_42.json");
+        let mut content = parse_tournament_data("", &data).unwrap().result;
+        assert!(!content.has_next());
+
+        content.last = None;
+        content.total_pages = Some(3);
+        assert!(content.has_next());
+    }
+
+    #[tokio::test]
+    async fn lists_the_remaining_matches_in_order() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+
+        let spire = Spire::new(&server.config());
+        let schedule = spire.get_schedule(42, 101).await.unwrap();
+        let titles: Vec<_> = schedule.iter().map(Bout::get_title).collect();
+
+        assert_eq!(
+            titles,
+            vec![
+                "Beserkers vs Team Liquid Nitrogen",
+                "Beserkers vs Cannon Rushers"
+            ]
+        );
+        assert!(spire.get_schedule(42, 999).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reports_the_result_of_the_last_match() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5000", "match_5000.json");
+
+        let spire = Spire::new(&server.config());
+        let outcome = spire.find_last_outcome(42, 101).await.unwrap();
+
+        assert_eq!(outcome.get_title(), "Zerg Rushers 1 - 3 Beserkers");
+        assert_eq!(outcome.get_winner(), Some("Beserkers"));
+        let maps = outcome.get_maps();
+        assert!(maps.contains("Eternal Empire LE: won by Zerg Rushers"));
+        assert!(maps.contains("Pillars of Gold LE: not played"));
+    }
+
+    #[tokio::test]
+    async fn has_no_result_for_unfinished_matches() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches/5001", "match_5001.json");
+
+        let spire = Spire::new(&server.config());
+        let why = spire.get_outcome(5001).await.unwrap_err();
+        assert!(matches!(why, ApiError::Unfinished { bout_id: 5001 }));
+        assert_eq!(spire.error_response(&why).title, "No result yet");
+    }
+
+    #[tokio::test]
+    async fn computes_the_standings_of_a_tournament() {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+
+        let spire = Spire::new(&server.config());
+        let standings = spire.get_standings(42, 101).await.unwrap();
+
+        assert_eq!(
+            standings.get_title(),
+            "Standings: Beserker Team League Season 3"
+        );
+        assert_eq!(standings.get_rows().len(), 4);
+        assert_eq!(standings.get_position(), Some(1));
+        assert!(standings
+            .get_table()
+            .contains("> 1  Beserkers             1  0  +2\n"));
+        assert!(spire.get_standings(42, 999).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn ranks_teams_within_their_group() {
+        let server = MockSpire::start().await;
+        let tournament = edit_fixture("tournament_42.json", |json| {
+            // 5000 and 5001 are played in the group, 5002 and 5003 in the playoffs
+            for bout in json["result"]["content"].as_array_mut().unwrap() {
+                bout["stage"] = match bout["id"].as_u64() {
+                    Some(5000) | Some(5001) => serde_json::json!({"id": 1, "name": "Group A"}),
+                    _ => serde_json::json!({"id": 2, "name": "Playoffs"}),
+                };
+            }
+        });
+        server.route("/matches?tournamentId=42&page=0", 200, &tournament);
+
+        let spire = Spire::new(&server.config());
+        let standings = spire.get_standings(42, 202).await.unwrap();
+        assert_eq!(
+            standings.get_title(),
+            "Standings: Beserker Team League Season 3, Group A"
+        );
+        assert_eq!(
+            standings.get_description(),
+            "Team Liquid Nitrogen is #2 of 3 in Group A"
+        );
+        assert!(standings
+            .get_table()
+            .contains("  1  Beserkers             1  0  +2\n"));
+
+        // the latest match of the Beserkers is in the playoffs
+        let standings = spire.get_standings(42, 101).await.unwrap();
+        assert_eq!(
+            standings.get_description(),
+            "Beserkers is #1 of 3 in Playoffs"
+        );
+        assert!(standings
+            .get_table()
+            .contains("> 1  Beserkers             0  0  +0\n"));
+    }
+
+    #[tokio::test]
+    async fn scouts_the_next_opponent() {
+        let server = MockSpire::start().await;
+        let tournament = edit_fixture("tournament_42.json", |json| {
+            // the Zerg Rushers are the next opponent
+            json["result"]["content"][2]["lineups"]["B"] =
+                json["result"]["content"][0]["lineups"]["A"].clone();
+        });
+        server.route("/matches?tournamentId=42&page=0", 200, &tournament);
+        server.fixture("/matches/5000", "match_5000.json");
+
+        let spire = Spire::new(&server.config());
+        let scouting = spire.scout_opponent(42, 101, None).await.unwrap();
+
+        assert_eq!(scouting.get_title(), "Scouting: Zerg Rushers");
+        assert_eq!(scouting.get_bouts(), "L 1-3 vs Beserkers");
+        assert!(scouting
+            .get_maps()
+            .starts_with("Eternal Empire LE: 1/1 (100%)"));
+        assert_eq!(scouting.get_roster(), "Serral, Reynor, Dark");
+    }
 }
@@ -1,16 +1,72 @@
-pub type Player = String;
 pub type MapName = String;
+
+/// The players of a map, one per slot, `None` for an open slot.
+pub type Slots = Vec<Option<Player>>;
 use crate::response::Response;
+use crate::rules::Rules;
 use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A Discord user signed up for a map, identified by their user id. The
+/// display name is the one they had when they last signed up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Player {
+    id: u64,
+    name: String,
+}
+
+impl Eq for Player {}
+
+impl PartialEq for Player {
+    /// The same user, whatever name they had.
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Player {
+    pub fn new(id: u64, name: String) -> Player {
+        Player { id, name }
+    }
 
-#[derive(Debug)]
+    /// Mentions the user.
+    pub fn mention(&self) -> String {
+        format!("<@{}>", self.id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bout {
     id: usize,
     tournament: String,
     datetime: DateTime<Local>,
-    maps: Vec<(MapName, Option<Player>)>,
+
+    /// The players of each map, one per slot.
+    maps: Vec<(MapName, Slots)>,
     home: String,
     away: String,
+
+    /// Players available as substitutes, in order of joining.
+    #[serde(default)]
+    bench: Vec<Player>,
+
+    /// Offset before the start of the last reminder sent for this bout.
+    #[serde(default)]
+    reminded: Option<Duration>,
+
+    /// Time before the start during which the players check in, zero if
+    /// they don't have to.
+    #[serde(default)]
+    checkin: Duration,
+
+    /// Players who checked in.
+    #[serde(default)]
+    confirmed: Vec<Player>,
+
+    /// Whether the players who didn't check in have been reported.
+    #[serde(default)]
+    no_shows_reported: bool,
 }
 
 impl Eq for Bout {}
@@ -31,7 +87,7 @@ impl Bout {
         away: String,
     ) -> Bout {
         let datetime = utc_datetime.with_timezone(&Local);
-        let maps = maps.into_iter().map(|map| (map, None)).collect();
+        let maps = maps.into_iter().map(|map| (map, vec![None])).collect();
         Bout {
             id,
             tournament,
@@ -39,12 +95,26 @@ impl Bout {
             maps,
             home,
             away,
+            bench: Vec::new(),
+            reminded: None,
+            checkin: Duration::from_secs(0),
+            confirmed: Vec::new(),
+            no_shows_reported: false,
         }
     }
 
+    /// Number of maps players can sign up for, including the ACE map, which
+    /// is the last one.
+    pub fn assignable_maps(&self) -> usize {
+        self.maps.len()
+    }
+
     fn is_valid_index(&self, index: usize) -> Result<(), Response> {
-        if index >= self.maps.len() || index == 0 {
-            let text = format!("Please enter a number between 1 and {}", self.maps.len() - 1);
+        if index > self.assignable_maps() || index == 0 {
+            let text = format!(
+                "Please enter a number between 1 and {}",
+                self.assignable_maps()
+            );
             let response = Response::new_error(String::from("Invalid index."), text);
             return Err(response);
         }
@@ -52,25 +122,354 @@ impl Bout {
         Ok(())
     }
 
-    pub fn insert_player(&mut self, index: usize, player: String) -> Result<(), Response> {
+    /// Checks that the map at `index` has a slot `slot`, counting from 1.
+    fn is_valid_slot(&self, index: usize, slot: usize) -> Result<(), Response> {
+        let slots = self.maps[index - 1].1.len();
+        if slot > slots || slot == 0 {
+            let text = format!("Please enter a slot between 1 and {}", slots);
+            return Err(Response::new_error(String::from("Invalid slot."), text));
+        }
+
+        Ok(())
+    }
+
+    /// Sets the number of players per map, keeping the players of the slots
+    /// that remain.
+    pub fn set_team_size(&mut self, size: usize) {
+        for (_, slots) in self.maps.iter_mut() {
+            slots.resize(size.max(1), None);
+        }
+    }
+
+    /// Picks the slot of the map at `index` for `player` if they didn't give
+    /// one: the slot they already have, the first open slot, or the only
+    /// slot of a 1v1 map. Only a player who `replaces` others may take the
+    /// latter.
+    fn pick_slot(&self, index: usize, player: &Player, replaces: bool) -> Result<usize, Response> {
+        if let Some(slot) = self.find_slot(index, player) {
+            return Ok(slot);
+        }
+        let slots = &self.maps[index - 1].1;
+        if let Some(i) = slots.iter().position(Option::is_none) {
+            return Ok(i + 1);
+        }
+        if slots.len() == 1 {
+            return Ok(1);
+        }
+
+        let name = &self.maps[index - 1].0;
+        let text = if replaces {
+            format!(
+                "All slots of {} are taken, please enter the slot to replace.",
+                name
+            )
+        } else {
+            format!("All slots of {} are taken.", name)
+        };
+        Err(Response::new_error(String::from("Map is full"), text))
+    }
+
+    /// Checks that `player` may sign up for `slot` of the map at `index`
+    /// under `rules`.
+    fn check_rules(
+        &self,
+        index: usize,
+        slot: usize,
+        player: &Player,
+        rules: &Rules,
+    ) -> Result<(), Response> {
+        let ace = self.maps.len();
+        let held: Vec<usize> = (1..=self.maps.len())
+            .filter(|i| *i == index || self.find_slot(*i, player).is_some())
+            .collect();
+        let limited: Vec<usize> = if rules.ace_exception {
+            held.iter().copied().filter(|i| *i != ace).collect()
+        } else {
+            held
+        };
+        if rules.one_map_per_player && limited.len() > 1 {
+            let other = limited.iter().find(|i| **i != index).unwrap();
+            let text = format!(
+                "{} already plays {}, and can only play one map. Withdraw from it first.",
+                player.mention(),
+                &self.maps[other - 1].0
+            );
+            return Err(Response::new_error(String::from("Already signed up"), text));
+        }
+
+        if let Some(max) = rules.max_players {
+            let mut players: Vec<&Player> = Vec::new();
+            let others = self.maps.iter().enumerate().flat_map(|(i, (_, slots))| {
+                slots
+                    .iter()
+                    .enumerate()
+                    .filter(move |(s, _)| (i + 1, s + 1) != (index, slot))
+                    .filter_map(|(_, player)| player.as_ref())
+            });
+            for other in others.chain(Some(player)) {
+                if !players.contains(&other) {
+                    players.push(other);
+                }
+            }
+            if players.len() > max {
+                let text = format!("At most {} players can play in a match.", max);
+                return Err(Response::new_error(String::from("Lineup is full"), text));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Signs `player` up for `slot` of the map at `index` if `rules` allow
+    /// it, and updates their name on the other maps they signed up for.
+    /// Without a slot, one is picked, see `pick_slot`. The player of a taken
+    /// slot is only replaced if `replaces` is set, as captains do.
+    pub fn insert_player(
+        &mut self,
+        index: usize,
+        slot: Option<usize>,
+        player: Player,
+        replaces: bool,
+        rules: &Rules,
+    ) -> Result<(), Response> {
         self.is_valid_index(index)?;
+        let slot = match slot {
+            Some(slot) => {
+                self.is_valid_slot(index, slot)?;
+                slot
+            }
+            None => self.pick_slot(index, &player, replaces)?,
+        };
+        match &self.maps[index - 1].1[slot - 1] {
+            Some(holder) if *holder != player && !replaces => {
+                let text = format!(
+                    "{} already plays {}, only a captain can replace them.",
+                    holder.mention(),
+                    &self.maps[index - 1].0
+                );
+                return Err(Response::new_error(String::from("Map taken"), text));
+            }
+            _ => {}
+        }
+        self.check_rules(index, slot, &player, rules)?;
 
-        self.maps[index - 1].1 = Some(player);
+        // a player switching slots leaves their previous slot of the map
+        if let Some(previous) = self.find_slot(index, &player) {
+            self.maps[index - 1].1[previous - 1] = None;
+        }
+        for signed_up in self
+            .maps
+            .iter_mut()
+            .flat_map(|(_, slots)| slots.iter_mut())
+            .flatten()
+        {
+            if *signed_up == player {
+                signed_up.name = player.name.clone();
+            }
+        }
+        self.bench.retain(|substitute| *substitute != player);
+        self.maps[index - 1].1[slot - 1] = Some(player);
         Ok(())
     }
 
-    pub fn remove_player(&mut self, index: usize) -> Result<(), Response> {
+    /// Adds `player` to the bench of substitutes, unless already on it.
+    pub fn join_bench(&mut self, player: Player) {
+        match self
+            .bench
+            .iter_mut()
+            .find(|substitute| **substitute == player)
+        {
+            Some(substitute) => substitute.name = player.name,
+            None => self.bench.push(player),
+        }
+    }
+
+    /// Removes `player` from the bench. Returns whether they were on it.
+    pub fn leave_bench(&mut self, player: &Player) -> bool {
+        let before = self.bench.len();
+        self.bench.retain(|substitute| substitute != player);
+        self.bench.len() < before
+    }
+
+    /// The substitutes on the bench, or a notice that nobody is.
+    pub fn get_bench(&self) -> String {
+        if self.bench.is_empty() {
+            return String::from("Nobody");
+        }
+        let players: Vec<_> = self.bench.iter().map(Player::mention).collect();
+        players.join(", ")
+    }
+
+    /// Sets the time before the start during which the players check in.
+    pub fn set_checkin(&mut self, window: Duration) {
+        self.checkin = window;
+    }
+
+    /// Whether the players can check in at `now`.
+    pub fn is_checkin_open(&self, now: DateTime<Local>) -> bool {
+        match self.datetime.signed_duration_since(now).to_std() {
+            Ok(remaining) => !self.checkin.is_zero() && remaining <= self.checkin,
+            // the bout has started
+            Err(_) => false,
+        }
+    }
+
+    /// Confirms that `player` plays the bout, which is only possible during
+    /// the check-in window and for players in the lineup.
+    pub fn check_in(&mut self, player: Player, now: DateTime<Local>) -> Result<(), Response> {
+        if self.checkin.is_zero() {
+            let text = String::from("The players of this team don't need to check in.");
+            return Err(Response::new_warning(String::from("No check-in"), text));
+        }
+        if !self.is_checkin_open(now) {
+            if now >= self.datetime {
+                let text = String::from("The match has started.");
+                return Err(Response::new_error(String::from("Check-in closed"), text));
+            }
+            let opens = chrono::Duration::from_std(self.checkin)
+                .ok()
+                .and_then(|window| self.datetime.checked_sub_signed(window));
+            let text = match opens {
+                Some(opens) => format!("Check-in opens {}.", opens.format("%A %B %d at %H:%M")),
+                None => String::from("Check-in opens before the match."),
+            };
+            return Err(Response::new_error(String::from("Check-in not open"), text));
+        }
+        if !self.get_players().contains(&&player) {
+            let text = String::from("Only the players in the lineup check in.");
+            return Err(Response::new_error(String::from("Not signed up"), text));
+        }
+
+        if !self.confirmed.contains(&player) {
+            self.confirmed.push(player);
+        }
+        Ok(())
+    }
+
+    /// The players in the lineup who haven't checked in, each once.
+    pub fn get_unconfirmed(&self) -> Vec<&Player> {
+        let mut unconfirmed: Vec<&Player> = Vec::new();
+        for player in self.get_players() {
+            if !self.confirmed.contains(player) && !unconfirmed.contains(&player) {
+                unconfirmed.push(player);
+            }
+        }
+        unconfirmed
+    }
+
+    /// The state of the check-in at `now`, `None` outside of the window.
+    pub fn get_checkin(&self, now: DateTime<Local>) -> Option<String> {
+        if !self.is_checkin_open(now) {
+            return None;
+        }
+        let unconfirmed = self.get_unconfirmed();
+        if unconfirmed.is_empty() {
+            return Some(String::from("Everyone checked in"));
+        }
+        let players: Vec<_> = unconfirmed.iter().map(|player| player.mention()).collect();
+        Some(format!("Waiting for {}", players.join(", ")))
+    }
+
+    /// Collects the players who didn't check in once the bout has started at
+    /// `now`. They are reported only once, so later calls return nothing.
+    pub fn take_no_shows(&mut self, now: DateTime<Local>) -> Vec<Player> {
+        if self.checkin.is_zero() || self.no_shows_reported || now < self.datetime {
+            return Vec::new();
+        }
+        self.no_shows_reported = true;
+        self.get_unconfirmed().into_iter().cloned().collect()
+    }
+
+    /// Suggests the substitutes on the bench for an open slot of the map at
+    /// `index`. `None` if the map is full or nobody is on the bench.
+    pub fn get_promotion(&self, index: usize) -> Option<String> {
+        let (map, slots) = self.maps.get(index.checked_sub(1)?)?;
+        if self.bench.is_empty() || !slots.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(format!(
+            "{} needs a player. Available on the bench: {}",
+            map,
+            self.get_bench()
+        ))
+    }
+
+    /// Clears `slot` of the map at `index`, or all of its slots if `slot` is
+    /// `None`.
+    pub fn remove_player(&mut self, index: usize, slot: Option<usize>) -> Result<(), Response> {
         self.is_valid_index(index)?;
 
-        self.maps[index - 1].1 = None;
+        match slot {
+            Some(slot) => {
+                self.is_valid_slot(index, slot)?;
+                self.maps[index - 1].1[slot - 1] = None;
+            }
+            None => {
+                for player in self.maps[index - 1].1.iter_mut() {
+                    *player = None;
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Copies the players of `previous` onto the maps of this bout that still
+    /// exist, matched by map name. Players of removed maps are dropped.
+    /// Reminders are sent again if the bout was rescheduled.
+    pub fn carry_over(&mut self, previous: Bout) {
+        if previous.datetime == self.datetime {
+            self.reminded = previous.reminded;
+            self.confirmed = previous.confirmed;
+            self.no_shows_reported = previous.no_shows_reported;
+        }
+        self.bench = previous.bench;
+        let mut remaining = previous.maps;
+        for (map, slots) in self.maps.iter_mut() {
+            if let Some(i) = remaining.iter().position(|(name, _)| name == map) {
+                *slots = remaining.remove(i).1;
+            }
+        }
+    }
+
+    /// Describes how this bout differs from `previous`, the same bout when it
+    /// was last fetched. Empty if nothing the players care about changed.
+    pub fn changes_since(&self, previous: &Bout) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.datetime != previous.datetime {
+            let format = "%A %B %d, %Y at %H:%M";
+            changes.push(format!(
+                "Moved from {} to {}",
+                previous.datetime.format(format),
+                self.datetime.format(format)
+            ));
+        }
+        if self.get_title() != previous.get_title() {
+            changes.push(format!(
+                "Opponent changed from {} to {}",
+                previous.get_title(),
+                self.get_title()
+            ));
+        }
+
+        let has_map = |bout: &Bout, map: &str| bout.maps.iter().any(|(name, _)| name == map);
+        for (map, _) in &previous.maps {
+            if !has_map(self, map) {
+                changes.push(format!("Map removed: {}", map));
+            }
+        }
+        for (map, _) in &self.maps {
+            if !has_map(previous, map) {
+                changes.push(format!("Map added: {}", map));
+            }
+        }
+        changes
+    }
+
     pub fn get_title(&self) -> String {
         format!("{} vs {}", &self.home, &self.away)
     }
 
-    pub fn get_description(&self) -> String {
+    pub fn get_description(&self, site_url: &str) -> String {
         let date = self.datetime.format("%A %B %d, %Y").to_string();
         let time = self.datetime.time().to_string();
         let remaining = self.datetime.signed_duration_since(Local::now());
@@ -78,34 +477,248 @@ impl Bout {
         let hours = remaining.num_hours() - days * 24;
         let min = remaining.num_minutes() - days * 24 * 60 - hours * 60;
 
-        let url = format!("https://spire.gg/match/{}", self.id);
+        let url = format!("{}/match/{}", site_url, self.id);
         format!(
             "Date: {}\nTime: {} (in: {}d {}hr {}min)\nChannel: spire{}\n{}",
             date, time, days, hours, min, self.id, url
         )
     }
 
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_datetime(&self) -> DateTime<Local> {
+        self.datetime
+    }
+
+    pub fn get_reminded(&self) -> Option<Duration> {
+        self.reminded
+    }
+
+    pub fn set_reminded(&mut self, offset: Duration) {
+        self.reminded = Some(offset);
+    }
+
+    /// The players signed up for the bout, in order of the maps.
+    pub fn get_players(&self) -> Vec<&Player> {
+        self.maps
+            .iter()
+            .flat_map(|(_, slots)| slots.iter().flatten())
+            .collect()
+    }
+
+    /// Whether a slot of a map has no player signed up yet.
+    pub fn has_open_maps(&self) -> bool {
+        self.maps
+            .iter()
+            .any(|(_, slots)| slots.iter().any(Option::is_none))
+    }
+
+    /// Gets the player signed up for `slot` of the map at `index`, both
+    /// counting from 1.
+    pub fn get_player_at(&self, index: usize, slot: usize) -> Option<&Player> {
+        let (_, slots) = self.maps.get(index.checked_sub(1)?)?;
+        slots.get(slot.checked_sub(1)?)?.as_ref()
+    }
+
+    /// Finds the slot of the map at `index` that `player` signed up for.
+    pub fn find_slot(&self, index: usize, player: &Player) -> Option<usize> {
+        let (_, slots) = self.maps.get(index.checked_sub(1)?)?;
+        let i = slots
+            .iter()
+            .position(|signed_up| signed_up.as_ref() == Some(player))?;
+        Some(i + 1)
+    }
+
+    /// Gets the players signed up for the map named `map`.
+    pub fn get_players_of(&self, map: &str) -> Vec<&Player> {
+        self.maps
+            .iter()
+            .filter(|(name, _)| name == map)
+            .flat_map(|(_, slots)| slots.iter().flatten())
+            .collect()
+    }
+
+    pub fn get_tournament(&self) -> &str {
+        &self.tournament
+    }
+
+    /// Short overview of the bout, for listing several bouts at once.
+    pub fn get_summary(&self, site_url: &str) -> String {
+        let date = self.datetime.format("%A %B %d, %Y").to_string();
+        let time = self.datetime.time().to_string();
+        let maps: Vec<_> = self.maps.iter().map(|(map, _)| map.as_str()).collect();
+
+        format!(
+            "{} at {}\nMaps: {}\n{}/match/{}",
+            date,
+            time,
+            maps.join(", "),
+            site_url,
+            self.id
+        )
+    }
+
+    /// Lists the players of each map, with `[n]` for an open slot of map
+    /// `n`, or `[n.s]` for the open slot `s` of a map with several slots.
+    /// Players who checked in are marked, as are those who still need to
+    /// while the check-in is open.
     pub fn get_maps(&self) -> String {
         let mut result = String::new();
         let maps = self.maps.len();
-
-        for i in 0..maps {
-            let map = &self.maps[i];
-            if let Some(name) = &map.1 {
-                result.push_str(name);
+        let checkin_open = self.is_checkin_open(Local::now());
+        let mention = |player: &Player| {
+            if self.confirmed.contains(player) {
+                format!("{} \u{2705}", player.mention())
+            } else if checkin_open {
+                format!("{} \u{23f3}", player.mention())
             } else {
-                result.push('[');
-                if i < maps - 1 {
-                    result.push_str(&(i + 1).to_string());
-                } else {
-                    result.push_str("ACE")
-                }
-                result.push(']');
+                player.mention()
             }
+        };
+
+        for (i, (map, slots)) in self.maps.iter().enumerate() {
+            let number = if i < maps - 1 {
+                (i + 1).to_string()
+            } else {
+                String::from("ACE")
+            };
+            let players: Vec<_> = slots
+                .iter()
+                .enumerate()
+                .map(|(s, player)| match player {
+                    Some(player) => mention(player),
+                    None if slots.len() == 1 => format!("[{}]", number),
+                    None => format!("[{}.{}]", number, s + 1),
+                })
+                .collect();
+            result.push_str(&players.join(", "));
             result.push_str(": ");
-            result.push_str(&map.0);
+            result.push_str(map);
             result.push('\n');
         }
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bout() -> Bout {
+        let maps = ["Death Aura LE", "Eternal Empire LE", "Ever Dream LE"];
+        Bout::new(
+            5001,
+            String::from("Beserker Team League"),
+            Utc.ymd(2099, 3, 20).and_hms(19, 0, 0),
+            maps.iter().map(|map| String::from(*map)).collect(),
+            String::from("Beserkers"),
+            String::from("Team Liquid"),
+        )
+    }
+
+    #[test]
+    fn enforces_the_lineup_rules() {
+        let mut bout = bout();
+        let alice = Player::new(1, String::from("alice"));
+        let bob = Player::new(2, String::from("bob"));
+        let mut rules = Rules::default();
+
+        bout.insert_player(1, None, alice.clone(), false, &rules)
+            .unwrap();
+        let why = bout
+            .insert_player(2, None, alice.clone(), false, &rules)
+            .unwrap_err();
+        assert_eq!(why.title, "Already signed up");
+        assert!(why.contents.contains("Death Aura LE"));
+
+        // the ACE map is the exception, unless disabled
+        bout.insert_player(3, None, alice.clone(), false, &rules)
+            .unwrap();
+        rules.ace_exception = false;
+        bout.remove_player(3, None).unwrap();
+        assert!(bout
+            .insert_player(3, None, alice.clone(), false, &rules)
+            .is_err());
+
+        rules.max_players = Some(1);
+        let why = bout.insert_player(2, None, bob, false, &rules).unwrap_err();
+        assert_eq!(why.title, "Lineup is full");
+
+        rules.one_map_per_player = false;
+        bout.insert_player(2, None, alice, false, &rules).unwrap();
+        assert_eq!(bout.get_players().len(), 2);
+    }
+
+    #[test]
+    fn fills_the_slots_of_a_map() {
+        let mut bout = bout();
+        bout.set_team_size(2);
+        let alice = Player::new(1, String::from("alice"));
+        let bob = Player::new(2, String::from("bob"));
+        let carol = Player::new(3, String::from("carol"));
+        let rules = Rules::default();
+
+        bout.insert_player(2, None, alice.clone(), false, &rules)
+            .unwrap();
+        bout.insert_player(2, None, bob.clone(), false, &rules)
+            .unwrap();
+        assert!(bout.get_maps().contains("<@1>, <@2>: Eternal Empire LE\n"));
+        assert!(bout.get_maps().contains("[1.1], [1.2]: Death Aura LE\n"));
+
+        let why = bout
+            .insert_player(2, None, carol.clone(), false, &rules)
+            .unwrap_err();
+        assert_eq!(why.title, "Map is full");
+        let why = bout
+            .insert_player(2, Some(1), carol.clone(), false, &rules)
+            .unwrap_err();
+        assert_eq!(why.title, "Map taken");
+        bout.insert_player(2, Some(1), carol, true, &rules).unwrap();
+        assert!(bout.get_maps().contains("<@3>, <@2>: Eternal Empire LE\n"));
+
+        // captains switch players between slots within a map
+        bout.insert_player(2, Some(1), bob.clone(), true, &rules)
+            .unwrap();
+        assert!(bout.get_maps().contains("<@2>, [2.2]: Eternal Empire LE\n"));
+        assert_eq!(bout.find_slot(2, &bob), Some(1));
+
+        bout.remove_player(2, None).unwrap();
+        assert!(bout.get_players().is_empty());
+        assert!(bout
+            .insert_player(2, Some(3), alice, false, &rules)
+            .is_err());
+    }
+
+    #[test]
+    fn checks_in_players_before_the_start() {
+        let mut bout = bout();
+        let alice = Player::new(1, String::from("alice"));
+        let bob = Player::new(2, String::from("bob"));
+        let carol = Player::new(3, String::from("carol"));
+        let rules = Rules::default();
+        bout.insert_player(1, None, alice.clone(), false, &rules)
+            .unwrap();
+        bout.insert_player(2, None, carol.clone(), false, &rules)
+            .unwrap();
+        bout.set_checkin(Duration::from_secs(60 * 60));
+        let at = |hour, min| {
+            Utc.ymd(2099, 3, 20)
+                .and_hms(hour, min, 0)
+                .with_timezone(&Local)
+        };
+
+        let why = bout.check_in(alice.clone(), at(17, 0)).unwrap_err();
+        assert_eq!(why.title, "Check-in not open");
+        bout.check_in(alice, at(18, 30)).unwrap();
+        let why = bout.check_in(bob, at(18, 30)).unwrap_err();
+        assert_eq!(why.title, "Not signed up");
+        assert_eq!(bout.get_checkin(at(18, 30)).unwrap(), "Waiting for <@3>");
+        assert_eq!(bout.get_checkin(at(17, 0)), None);
+
+        assert!(bout.take_no_shows(at(18, 59)).is_empty());
+        assert_eq!(bout.take_no_shows(at(19, 0)), vec![carol]);
+        assert!(bout.take_no_shows(at(19, 1)).is_empty());
+    }
+}
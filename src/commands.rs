@@ -0,0 +1,291 @@
+use crate::bout::Player;
+use crate::response::Response;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Actions the bot can perform.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InternalCommand {
+    /// Removes a player from a tournament bout, given a tournament and team id.
+    Remove(usize, usize),
+
+    /// Adds a player to a trounament bout, given a tournament and team id.
+    Insert(usize, usize),
+
+    /// Shows the remaining bouts of a team, given a tournament and team id.
+    Schedule(usize, usize),
+
+    /// Shows the result of the last finished bout of a team, given a
+    /// tournament and team id.
+    Results(usize, usize),
+
+    /// Shows the standings of a tournament, given a tournament and team id.
+    Standings(usize, usize),
+
+    /// Shows the recent form of the opponent of the tracked bout, given a
+    /// tournament and team id.
+    Scout(usize, usize),
+
+    /// Polls spire.gg for changes of the next bout of a team, given a
+    /// tournament and team id.
+    Poll(usize, usize),
+
+    /// Adds a player to or removes them from the bench of substitutes of a
+    /// tournament bout, given a tournament and team id.
+    Bench(usize, usize),
+
+    /// Confirms that a player plays a tournament bout, given a tournament and
+    /// team id.
+    CheckIn(usize, usize),
+}
+
+impl InternalCommand {
+    /// The tournament and team id the command operates on.
+    pub fn ids(&self) -> (usize, usize) {
+        match self {
+            InternalCommand::Remove(tournament_id, team_id)
+            | InternalCommand::Insert(tournament_id, team_id)
+            | InternalCommand::Schedule(tournament_id, team_id)
+            | InternalCommand::Results(tournament_id, team_id)
+            | InternalCommand::Standings(tournament_id, team_id)
+            | InternalCommand::Scout(tournament_id, team_id)
+            | InternalCommand::Poll(tournament_id, team_id)
+            | InternalCommand::Bench(tournament_id, team_id)
+            | InternalCommand::CheckIn(tournament_id, team_id) => (*tournament_id, *team_id),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// Additional arguments to process internal commands
+pub enum Arguments {
+    /// Removes a player at a specified index and optionally slot. Unless
+    /// `None`, only the given player can be removed, as non-captains can only
+    /// withdraw themselves.
+    Remove(usize, Option<usize>, Option<Player>),
+
+    /// Insert a player at a specified index and optionally slot, replacing
+    /// the player of a taken slot if `true`, as only captains can.
+    Insert(Player, usize, Option<usize>, bool),
+
+    /// Show a specified page
+    Page(usize),
+
+    /// Adds a player to the bench if `true`, otherwise removes them
+    Bench(Player, bool),
+
+    /// Checks in a player
+    CheckIn(Player),
+}
+
+/// Dynamic list of Discord commands.
+#[derive(Serialize, Deserialize)]
+pub struct DiscordCommands {
+    commands: HashMap<String, InternalCommand>,
+}
+
+impl DiscordCommands {
+    /// Constructs a new empty list of Discord commands.
+    pub fn new() -> DiscordCommands {
+        DiscordCommands {
+            commands: HashMap::new(),
+        }
+    }
+
+    /// Adds a new command to the bot. Note, `discord_command` should not be
+    /// prefixed.
+    pub fn add_command(&mut self, discord_command: String, command: InternalCommand) {
+        self.commands.insert(discord_command, command);
+    }
+
+    /// Removes a command from the bot. Note, `discord_command` should not be
+    /// prefixed.
+    pub fn remove_command(&mut self, discord_command: &str) -> Option<InternalCommand> {
+        self.commands.remove(discord_command)
+    }
+
+    /// Gets the associated command given the string. Note, `discord_command`
+    /// should not be prefixed.
+    pub fn get(&self, discord_command: &str) -> Option<&InternalCommand> {
+        self.commands.get(discord_command)
+    }
+
+    /// Iterates over the actions of all commands.
+    pub fn actions(&self) -> impl Iterator<Item = &InternalCommand> {
+        self.commands.values()
+    }
+}
+
+/// Split message contents by `' '`.
+pub fn get_msg_words(contents: &str) -> Vec<&str> {
+    contents.split(' ').collect()
+}
+
+/// Prefixes `command` with the identifier if the user hasn't done that.
+pub fn prefixed(command: &str) -> String {
+    if command.starts_with('!') {
+        String::from(command)
+    } else {
+        format!("!{}", command)
+    }
+}
+
+/// Parses a whole positive number, or explains to the user why it isn't one.
+fn parse_number(word: &str) -> Result<usize, Response> {
+    word.parse::<usize>().map_err(|why| {
+        let title = String::from("Please enter a whole positive number");
+        Response::new_error(title, why.to_string())
+    })
+}
+
+/// Parses a role given as a mention (`<@&id>`) or a plain id. `none` clears
+/// the role.
+pub fn parse_role(word: &str) -> Result<Option<u64>, Response> {
+    if word.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let id = word.trim_start_matches("<@&").trim_end_matches('>');
+    match id.parse::<u64>() {
+        Ok(id) => Ok(Some(id)),
+        Err(_) => {
+            let title = String::from("Invalid role");
+            let text = String::from("Please mention the role, or enter its id or `none`");
+            Err(Response::new_error(title, text))
+        }
+    }
+}
+
+/// Parses a user mention (`<@id>` or `<@!id>`) into the id of the user.
+pub fn parse_mention(word: &str) -> Option<u64> {
+    let id = word.strip_prefix("<@")?.strip_suffix('>')?;
+    id.trim_start_matches('!').parse().ok()
+}
+
+/// Names of the actions of `InternalCommand`, as used to add a command.
+pub const ACTIONS: [&str; 9] = [
+    "insert",
+    "remove",
+    "schedule",
+    "results",
+    "standings",
+    "scout",
+    "poll",
+    "bench",
+    "checkin",
+];
+
+/// Parses the name of an action into the constructor of its command, which
+/// takes a tournament and team id.
+pub fn parse_action(action: &str) -> Result<fn(usize, usize) -> InternalCommand, Response> {
+    match action.to_lowercase().as_ref() {
+        "insert" => Ok(InternalCommand::Insert),
+        "remove" => Ok(InternalCommand::Remove),
+        "schedule" => Ok(InternalCommand::Schedule),
+        "results" => Ok(InternalCommand::Results),
+        "standings" => Ok(InternalCommand::Standings),
+        "scout" => Ok(InternalCommand::Scout),
+        "poll" => Ok(InternalCommand::Poll),
+        "bench" => Ok(InternalCommand::Bench),
+        "checkin" => Ok(InternalCommand::CheckIn),
+        _ => {
+            let title = String::from("Invalid command");
+            let text = String::from("Expected: `!add_command <new_command> <action> [args].`\nInvalid action, please use one of `insert`, `remove`, `schedule`, `results`, `standings`, `scout`, `poll`, `bench`, or `checkin`.");
+            Err(Response::new_error(title, text))
+        }
+    }
+}
+
+/// Parses the words of `!add_command <new_command> <action> [args]` into the
+/// new (prefixed) command and its action.
+pub fn parse_add_command(words: &[&str]) -> Result<(String, InternalCommand), Response> {
+    if words.len() < 3 {
+        let title = String::from("Not enough identifier");
+        let text = String::from("Expected `!add_command <new_command> <action> [args]`");
+        return Err(Response::new_error(title, text));
+    }
+
+    let new_command = prefixed(words[1]);
+    let command = parse_action(words[2])?;
+
+    // !add_command <new_command> <action> <team_id> <tournament_id>
+    if words.len() < 5 {
+        let title = String::from("Not enough arguments");
+        let text = format!("Expected 5 arguments, received {}", words.len() - 1);
+        return Err(Response::new_error(title, text));
+    }
+
+    let team_id = parse_number(words[3])?;
+    let tournament_id = parse_number(words[4])?;
+    Ok((new_command, command(tournament_id, team_id)))
+}
+
+/// Parses the words following a dynamic command into the arguments of
+/// `command`. `player` is the one who sent the message and `assignee` the
+/// player they mentioned, whom only a `captain` of the team may assign.
+pub fn parse_arguments(
+    command: &InternalCommand,
+    words: &[&str],
+    player: &Player,
+    assignee: Option<&Player>,
+    captain: bool,
+) -> Result<Option<Arguments>, Response> {
+    if let InternalCommand::Bench(_, _) = command {
+        let leaves = matches!(words.get(1), Some(word) if word.eq_ignore_ascii_case("leave"));
+        return Ok(Some(Arguments::Bench(player.clone(), !leaves)));
+    }
+    if let InternalCommand::CheckIn(_, _) = command {
+        return Ok(Some(Arguments::CheckIn(player.clone())));
+    }
+    if words.len() < 2 {
+        return Ok(None);
+    }
+    if matches!(
+        command,
+        InternalCommand::Results(_, _)
+            | InternalCommand::Standings(_, _)
+            | InternalCommand::Scout(_, _)
+            | InternalCommand::Poll(_, _)
+    ) {
+        return Ok(None);
+    }
+
+    let index = parse_number(words[1])?;
+    // the slot may follow the map, before a mentioned player
+    let slot = match words.get(2) {
+        Some(word) if !word.starts_with("<@") => Some(parse_number(word)?),
+        _ => None,
+    };
+    match command {
+        InternalCommand::Insert(_, _) => {
+            let assignee = assignee.unwrap_or(player);
+            if assignee != player && !captain {
+                return Err(not_captain());
+            }
+            Ok(Some(Arguments::Insert(
+                assignee.clone(),
+                index,
+                slot,
+                captain,
+            )))
+        }
+        InternalCommand::Remove(_, _) => {
+            let player = if captain { None } else { Some(player.clone()) };
+            Ok(Some(Arguments::Remove(index, slot, player)))
+        }
+        InternalCommand::Schedule(_, _) => Ok(Some(Arguments::Page(index))),
+        InternalCommand::Results(_, _)
+        | InternalCommand::Standings(_, _)
+        | InternalCommand::Scout(_, _)
+        | InternalCommand::Poll(_, _)
+        | InternalCommand::Bench(_, _)
+        | InternalCommand::CheckIn(_, _) => Ok(None),
+    }
+}
+
+/// The reply to a player who tried to change the map of another player
+/// without being a captain.
+pub fn not_captain() -> Response {
+    let title = String::from("Not a captain");
+    let text = String::from("Only captains can sign up or remove other players.");
+    Response::new_error(title, text)
+}
@@ -0,0 +1,154 @@
+use serenity::model::permissions::Permissions;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Settings of the bot, read from the environment at startup.
+pub struct Config {
+    /// Path of the JSON file in which the state is stored between restarts.
+    pub storage_path: String,
+
+    /// Base URL of the spire.gg API.
+    pub api_url: String,
+
+    /// Base URL of the spire.gg website.
+    pub site_url: String,
+
+    /// Time after which a request to spire.gg is abandoned.
+    pub timeout: Duration,
+
+    /// Number of times a failed request to spire.gg is retried.
+    pub retries: u32,
+
+    /// Delay before the first retry, doubled for every next retry.
+    pub backoff: Duration,
+
+    /// Time for which responses of spire.gg are reused. Zero disables the
+    /// cache.
+    pub cache_ttl: Duration,
+
+    /// Time after its start during which a match is still considered to be
+    /// the next match.
+    pub grace: Duration,
+
+    /// Offsets before the start of a match at which a reminder is posted.
+    pub reminders: Vec<Duration>,
+
+    /// Interval at which teams with a `poll` command are refreshed.
+    pub poll_interval: Duration,
+
+    /// Whether the lineup embed of a bout is pinned in its channel.
+    pub pin_lineups: bool,
+
+    /// Permission that allows to manage the commands of a server, next to
+    /// the admin role of the server.
+    pub admin_permission: Permissions,
+}
+
+impl Config {
+    /// Reads the configuration from the environment, using the default for
+    /// every variable that isn't set.
+    pub fn from_env() -> Config {
+        let default = Config::default();
+        Config {
+            storage_path: var_or("STORAGE_PATH", default.storage_path),
+            api_url: var_or("SPIRE_API_URL", default.api_url),
+            site_url: var_or("SPIRE_URL", default.site_url),
+            timeout: Duration::from_secs(var_or("SPIRE_TIMEOUT_SECS", default.timeout.as_secs())),
+            retries: var_or("SPIRE_RETRIES", default.retries),
+            backoff: Duration::from_millis(var_or(
+                "SPIRE_BACKOFF_MS",
+                default.backoff.as_millis() as u64,
+            )),
+            cache_ttl: Duration::from_secs(var_or("SPIRE_CACHE_SECS", default.cache_ttl.as_secs())),
+            grace: Duration::from_secs(
+                60 * var_or("MATCH_GRACE_MINS", default.grace.as_secs() / 60),
+            ),
+            reminders: mins_or("REMINDER_MINS", default.reminders),
+            poll_interval: Duration::from_secs(
+                60 * var_or("POLL_MINS", default.poll_interval.as_secs() / 60),
+            ),
+            pin_lineups: var_or("PIN_LINEUPS", default.pin_lineups),
+            admin_permission: permission_or("ADMIN_PERMISSION", default.admin_permission),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            storage_path: String::from("beserker.json"),
+            api_url: String::from("https://api.spire.gg"),
+            site_url: String::from("https://spire.gg"),
+            timeout: Duration::from_secs(10),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            cache_ttl: Duration::from_secs(60),
+            grace: Duration::from_secs(2 * 60 * 60),
+            reminders: vec![
+                Duration::from_secs(24 * 60 * 60),
+                Duration::from_secs(60 * 60),
+                Duration::from_secs(15 * 60),
+            ],
+            poll_interval: Duration::from_secs(15 * 60),
+            pin_lineups: false,
+            admin_permission: Permissions::MANAGE_GUILD,
+        }
+    }
+}
+
+/// Gets the environment variable `key`, or `default` if it isn't set.
+///
+/// # Panics
+/// Panics if the variable is set, but cannot be parsed.
+fn var_or<T: FromStr>(key: &str, default: T) -> T {
+    match env::var(key) {
+        Ok(value) => match value.parse() {
+            Ok(parsed) => parsed,
+            Err(_) => panic!("Invalid value for {}: \"{}\"", key, value),
+        },
+        Err(_) => default,
+    }
+}
+
+/// Gets the environment variable `key` as a comma separated list of
+/// minutes, or `default` if it isn't set. An empty list is allowed.
+///
+/// # Panics
+/// Panics if the variable is set, but cannot be parsed.
+fn mins_or(key: &str, default: Vec<Duration>) -> Vec<Duration> {
+    match env::var(key) {
+        Ok(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|mins| !mins.is_empty())
+            .map(|mins| match mins.parse::<u64>() {
+                Ok(mins) => Duration::from_secs(60 * mins),
+                Err(_) => panic!("Invalid value for {}: \"{}\"", key, value),
+            })
+            .collect(),
+        Err(_) => default,
+    }
+}
+
+/// Gets the environment variable `key` as the name of a Discord permission,
+/// e.g. `MANAGE_GUILD`, or `default` if it isn't set.
+///
+/// # Panics
+/// Panics if the variable is set, but isn't a known permission.
+fn permission_or(key: &str, default: Permissions) -> Permissions {
+    let value = match env::var(key) {
+        Ok(value) => value,
+        Err(_) => return default,
+    };
+    match value.trim().to_uppercase().as_str() {
+        "ADMINISTRATOR" => Permissions::ADMINISTRATOR,
+        "MANAGE_GUILD" => Permissions::MANAGE_GUILD,
+        "MANAGE_CHANNELS" => Permissions::MANAGE_CHANNELS,
+        "MANAGE_ROLES" => Permissions::MANAGE_ROLES,
+        "MANAGE_MESSAGES" => Permissions::MANAGE_MESSAGES,
+        "KICK_MEMBERS" => Permissions::KICK_MEMBERS,
+        "BAN_MEMBERS" => Permissions::BAN_MEMBERS,
+        _ => panic!("Invalid value for {}: \"{}\"", key, value),
+    }
+}
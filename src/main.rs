@@ -1,16 +1,53 @@
+mod admin;
 mod api;
 mod bout;
+mod commands;
+mod config;
+mod outcome;
+mod processor;
+mod reminder;
 mod response;
-
-use bout::Bout;
+mod rules;
+mod schedule;
+mod scouting;
+mod slash;
+mod standings;
+mod storage;
+
+#[cfg(test)]
+mod mock;
+
+use admin::ADMIN_CHECK;
+use api::Spire;
+use bout::{Bout, Player};
+use chrono::prelude::*;
+use commands::{Arguments, DiscordCommands, InternalCommand};
+use config::Config;
+use outcome::Outcome;
+use processor::{Processor, Reply};
+use reminder::Reminder;
 use response::{Response, ResponseType};
+use schedule::Schedule;
+use scouting::Scouting;
+use serde::{Deserialize, Serialize};
+use standings::Standings;
+use std::collections::HashMap;
 use std::env;
-use std::{collections::HashMap, usize};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use storage::Storage;
 
 use serenity::{
     async_trait,
+    builder::CreateEmbed,
     http::Http,
-    model::{channel::Message, gateway::Ready},
+    model::{
+        channel::{Message, Reaction, ReactionType},
+        gateway::Ready,
+        id::{ChannelId, GuildId, MessageId, RoleId},
+        interactions::Interaction,
+    },
     prelude::*,
     utils::Colour,
 };
@@ -21,238 +58,226 @@ use serenity::framework::standard::{
     CommandResult, StandardFramework,
 };
 
+/// Interval at which the tracked bouts are checked for due reminders.
+const REMINDER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long after their start tracked bouts are checked for a result.
+const RESULTS_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Reactions to a lineup embed to sign up for the map with the same number.
+const MAP_EMOJIS: [&str; 10] = [
+    "1\u{fe0f}\u{20e3}",
+    "2\u{fe0f}\u{20e3}",
+    "3\u{fe0f}\u{20e3}",
+    "4\u{fe0f}\u{20e3}",
+    "5\u{fe0f}\u{20e3}",
+    "6\u{fe0f}\u{20e3}",
+    "7\u{fe0f}\u{20e3}",
+    "8\u{fe0f}\u{20e3}",
+    "9\u{fe0f}\u{20e3}",
+    "\u{1f51f}",
+];
+
 /// The message handler. Contains the list of Discord commands and the internal
 /// state of all the bouts.
 struct Handler;
 
-/// Actions the bot can perform.
-#[derive(Debug)]
-enum InternalCommand {
-    /// Removes a player from a tournament bout, given a tournament and team id.
-    Remove(usize, usize),
+/// Identifies which commands and bouts a message operates on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum Scope {
+    /// A Discord server.
+    Guild(GuildId),
 
-    /// Adds a player to a trounament bout, given a tournament and team id.
-    Insert(usize, usize),
+    /// A direct message channel, for messages sent outside of a server.
+    Direct(ChannelId),
 }
 
-#[derive(Debug)]
-/// Additional arguments to process internal commands
-enum Arguments {
-    #[allow(dead_code)]
-    /// Removes a player at a specified index
-    Remove(usize),
+impl Scope {
+    /// Gets the scope a message was sent in.
+    pub fn of(msg: &Message) -> Scope {
+        Scope::new(msg.guild_id, msg.channel_id)
+    }
 
-    /// Insert a player given by a String at a specified index
-    Insert(String, usize),
+    /// Gets the scope of a channel, which is part of a server unless
+    /// `guild_id` is `None`.
+    pub fn new(guild_id: Option<GuildId>, channel_id: ChannelId) -> Scope {
+        match guild_id {
+            Some(guild_id) => Scope::Guild(guild_id),
+            None => Scope::Direct(channel_id),
+        }
+    }
 }
 
-/// Dynamic list of Discord commands.
-struct DiscordCommands {
-    commands: HashMap<String, InternalCommand>,
+/// The embed showing the lineup of a bout, edited whenever the lineup
+/// changes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct LineupMessage {
+    channel: ChannelId,
+    message: MessageId,
 }
 
-impl DiscordCommands {
-    /// Constructs a new empty list of Discord commands.
-    pub fn new() -> DiscordCommands {
-        DiscordCommands {
-            commands: HashMap::new(),
-        }
-    }
+/// The commands and bouts of a single scope.
+#[derive(Serialize, Deserialize)]
+struct Community {
+    commands: DiscordCommands,
+    processor: Processor,
 
-    /// Adds a new command to the bot. Note, `discord_command` should not be
-    /// prefixed.
-    pub fn add_command(&mut self, discord_command: String, command: InternalCommand) {
-        self.commands.insert(discord_command, command);
-    }
+    /// The channel in which each team last used a command, where its
+    /// reminders are posted.
+    #[serde(with = "storage::pairs", default)]
+    channels: HashMap<(usize, usize), ChannelId>,
 
-    /// Removes a command from the bot. Note, `discord_command` should not be
-    /// prefixed.
-    pub fn remove_command(&mut self, discord_command: &str) -> Option<InternalCommand> {
-        self.commands.remove(discord_command)
-    }
+    /// The lineup embed of each tracked bout, by bout id.
+    #[serde(default)]
+    lineups: HashMap<usize, LineupMessage>,
 
-    /// Gets the associated command given the string. Note, `discord_command`
-    /// should not be prefixed.
-    pub fn get(&self, discord_command: &str) -> Option<&InternalCommand> {
-        self.commands.get(discord_command)
-    }
-}
+    /// Role of the captains of each team, who may sign up and remove other
+    /// players.
+    #[serde(with = "storage::pairs", default)]
+    captains: HashMap<(usize, usize), RoleId>,
 
-/// Data structure to keep track of all the active bouts per tournament per
-/// team.
-struct Processor {
-    bouts: HashMap<(usize, usize), Bout>,
+    /// Role whose members may manage the commands, next to the server
+    /// admins.
+    #[serde(default)]
+    admin_role: Option<RoleId>,
 }
 
-impl Processor {
-    pub fn new() -> Processor {
-        Processor {
-            bouts: HashMap::new(),
+impl Community {
+    /// Constructs a community without any commands or bouts.
+    pub fn new() -> Community {
+        Community {
+            commands: DiscordCommands::new(),
+            processor: Processor::new(),
+            channels: HashMap::new(),
+            lineups: HashMap::new(),
+            captains: HashMap::new(),
+            admin_role: None,
         }
     }
 
-    /// Handles the command and updates internal state if necessary.
-    pub async fn process(
-        &mut self,
-        ctx: &Context,
-        msg: &Message,
-        command: &InternalCommand,
-        args: Option<Arguments>,
-    ) {
-        match command {
-            InternalCommand::Remove(tournament_id, team_id) => {
-                self.remove(*tournament_id, *team_id, ctx, msg, args).await;
-            }
-            InternalCommand::Insert(tournament_id, team_id) => {
-                self.insert(*tournament_id, *team_id, ctx, msg, args).await;
-            }
+    /// Whether a member with `roles` is a captain of the team with `ids`.
+    fn is_captain(&self, ids: (usize, usize), roles: &[RoleId]) -> bool {
+        match self.captains.get(&ids) {
+            Some(role) => roles.contains(role),
+            None => false,
         }
     }
 
-    pub fn drop_entry(&mut self, id: (usize, usize)) -> Option<Bout> {
-        self.bouts.remove(&id)
-    }
-
-    /// Removes a player from a bout, identified by `tournament_id` and
-    /// `team_id`, at a specified index. Requires `args` to be
-    /// `Some(Arguments::Remove(index))`. In case `args` is incorrect, write
-    /// an appropriate error.
-    async fn remove(
+    /// Adds `command` under the (prefixed) name `name`. Polled teams post
+    /// their changes in `channel`, where the command was added.
+    fn add_command(
         &mut self,
-        tournament_id: usize,
-        team_id: usize,
-        ctx: &Context,
-        msg: &Message,
-        args: Option<Arguments>,
-    ) {
-        match args {
-            Some(op) => match op {
-                Arguments::Remove(index) => match self.bouts.get_mut(&(tournament_id, team_id)) {
-                    Some(bout) => {
-                        if let Err(why) = bout.remove_player(index) {
-                            let status = send_message_embed(why, msg, &ctx.http).await;
-                            if let Err(why) = status {
-                                println!("Error sending message: {:?}", why);
-                            }
-                        }
-                    }
-                    None => {
-                        let title = String::from("No active matches found");
-                        let message = format!(
-                            "For further information see https://spire.gg/tournament/{}#brackets.",
-                            tournament_id
-                        );
-                        let response = Response::new_error(title, message);
-                        let status = send_message_embed(response, msg, &ctx.http).await;
-                        if let Err(why) = status {
-                            println!("Error sending message: {:?}", why);
-                        }
-                        return;
-                    }
-                },
-                _ => {}
-            },
-            None => {
-                let title = String::from("Missing map number");
-                let text = String::from("Please specify which map to remove a player from");
-                let response = Response::new_error(title, text);
-
-                let status = send_message_embed(response, msg, &ctx.http).await;
-                if let Err(why) = status {
-                    println!("Error sending message: {:?}", why);
-                }
-                return;
-            }
+        name: String,
+        command: InternalCommand,
+        channel: ChannelId,
+    ) -> Response {
+        if let InternalCommand::Poll(_, _) = command {
+            self.channels.insert(command.ids(), channel);
         }
-        let bout = self.bouts.get(&(tournament_id, team_id)).unwrap();
+        self.commands.add_command(name.clone(), command);
 
-        let status = send_bout_embed(msg, &ctx.http, &bout).await;
-        if let Err(why) = status {
-            println!("Error sending message: {:?}", why);
-        }
+        let title = String::from("Added command");
+        let text = format!("Sucessfully added command `{}`.", name);
+        Response::new_success(title, text)
     }
 
-    /// Inserts a player into a bout, identified by `tournament_id` and
-    /// `team_id`, at a specified index. Requires `args` to be
-    /// `Some(Arguments::Remove(player, index))`. In case `args` is incorrect, write
-    /// an appropriate error.
-    async fn insert(
-        &mut self,
-        tournament_id: usize,
-        team_id: usize,
-        ctx: &Context,
-        msg: &Message,
-        args: Option<Arguments>,
-    ) {
-        // first update the bout / insert a new bout
-        if let Some(bout) = self.bouts.get_mut(&(tournament_id, team_id)) {
-            let result = api::find_next_bout(tournament_id, team_id).await;
-
-            match result {
-                Ok(next_bout) => {
-                    if *bout != next_bout {
-                        *bout = next_bout;
+    /// Removes the command with the (prefixed) name `name`, and stops
+    /// tracking the bout of a removed `insert` command. The channel of the
+    /// team is kept while any other command for it remains.
+    fn remove_command(&mut self, name: &str) -> Response {
+        match self.commands.remove_command(name) {
+            Some(internal_command) => {
+                if let InternalCommand::Insert(tournament_id, team_id) = internal_command {
+                    let ids = (tournament_id, team_id);
+                    let remaining: Vec<_> = self
+                        .commands
+                        .actions()
+                        .filter(|action| action.ids() == ids)
+                        .collect();
+                    let inserts = remaining
+                        .iter()
+                        .any(|action| matches!(action, InternalCommand::Insert(_, _)));
+                    if !inserts {
+                        self.processor.drop_entry(ids);
                     }
-                }
-                Err(why) => {
-                    let status = send_message_embed(why, msg, &ctx.http).await;
-                    if let Err(why) = status {
-                        println!("Error sending message: {:?}", why);
+                    if remaining.is_empty() {
+                        self.channels.remove(&ids);
                     }
-                    return;
                 }
+
+                let text = format!("Succesfully removed command `{}`.", name);
+                Response::new_success(String::from("Removed command"), text)
             }
-        } else {
-            let result = api::find_next_bout(tournament_id, team_id).await;
-            match result {
-                Ok(next_bout) => {
-                    self.bouts.insert((tournament_id, team_id), next_bout);
-                }
-                Err(why) => {
-                    let status = send_message_embed(why, msg, &ctx.http).await;
-                    if let Err(why) = status {
-                        println!("Error sending message: {:?}", why);
-                    }
-                    return;
-                }
+            None => {
+                let text = format!("The command `{}` could not be found.", name);
+                Response::new_warning(String::from("Command not found"), text)
             }
         }
+    }
 
-        // no errors so we can unwrap safely
-        let bout = self.bouts.get_mut(&(tournament_id, team_id)).unwrap();
-
-        match args {
-            Some(Arguments::Insert(player, index)) => {
-                if let Err(why) = bout.insert_player(index, player) {
-                    let status = send_message_embed(why, msg, &ctx.http).await;
-                    if let Err(why) = status {
-                        println!("Error sending message: {:?}", why);
-                    }
-                }
+    /// Forgets the lineup embeds of the bouts that are no longer tracked,
+    /// returning them.
+    fn prune_lineups(&mut self) -> Vec<LineupMessage> {
+        let processor = &self.processor;
+        let mut pruned = Vec::new();
+        self.lineups.retain(|bout_id, posted| {
+            let tracked = processor.is_tracked(*bout_id);
+            if !tracked {
+                pruned.push(*posted);
             }
-            _ => {}
-        }
-
-        let status = send_bout_embed(msg, &ctx.http, &bout).await;
-        if let Err(why) = status {
-            println!("Error sending message: {:?}", why);
-        }
+            tracked
+        });
+        pruned
     }
 }
 
 /// Simple wrapper which is dumped in the context data. The wrapper is nice
 /// to simplify ownership details.
+#[derive(Serialize)]
 struct Wrapper {
-    commands: DiscordCommands,
-    processor: Processor,
+    #[serde(with = "storage::pairs")]
+    communities: HashMap<Scope, Community>,
+    #[serde(skip_serializing)]
+    storage: Storage,
+}
+
+/// State of the wrapper as read back from storage.
+#[derive(Deserialize)]
+struct StoredWrapper {
+    #[serde(with = "storage::pairs")]
+    communities: HashMap<Scope, Community>,
 }
 
 impl Wrapper {
     /// Wrapper constructor.
-    pub fn new() -> Wrapper {
+    pub fn new(storage: Storage) -> Wrapper {
         Wrapper {
-            commands: DiscordCommands::new(),
-            processor: Processor::new(),
+            communities: HashMap::new(),
+            storage,
+        }
+    }
+
+    /// Gets the commands and bouts of `scope`, creating them on first use.
+    pub fn community_mut(&mut self, scope: Scope) -> &mut Community {
+        self.communities.entry(scope).or_insert_with(Community::new)
+    }
+
+    /// Restores the wrapper from `storage`, or constructs an empty wrapper if
+    /// nothing has been saved yet.
+    pub fn load(storage: Storage) -> io::Result<Wrapper> {
+        match storage.load::<StoredWrapper>()? {
+            Some(stored) => Ok(Wrapper {
+                communities: stored.communities,
+                storage,
+            }),
+            None => Ok(Wrapper::new(storage)),
+        }
+    }
+
+    /// Writes the commands and bouts to storage.
+    pub fn save(&self) {
+        if let Err(why) = self.storage.save(self) {
+            println!("Error saving state: {:?}", why);
         }
     }
 }
@@ -261,6 +286,14 @@ impl TypeMapKey for Wrapper {
     type Value = Self;
 }
 
+impl TypeMapKey for Spire {
+    type Value = Arc<Spire>;
+}
+
+impl TypeMapKey for Config {
+    type Value = Arc<Config>;
+}
+
 #[async_trait]
 impl EventHandler for Handler {
     // Set a handler for the `message` event - so that whenever a new message
@@ -271,99 +304,96 @@ impl EventHandler for Handler {
 
     /// Handles processing of custom commands.
     async fn message(&self, ctx: Context, msg: Message) {
-        // Extract the state from the context
-        let mut data = ctx.data.write().await;
-        let wrapper = data.get_mut::<Wrapper>().unwrap();
-        let commands = &wrapper.commands;
-
         // Ensure that the command does not overlap with the admin commands
         if !msg.content.starts_with('!')
             || msg.content.starts_with("!add_command")
             || msg.content.starts_with("!remove_command")
+            || msg.content.starts_with("!clear_cache")
+            || msg.content.starts_with("!admin_role")
+            || msg.content.starts_with("!captain_role")
+            || msg.content.starts_with("!lineup_rules")
         {
             return;
         }
 
-        let words = get_msg_words(&msg.content);
+        // Extract the commands of the guild from the context
+        let data = ctx.data.read().await;
+        let wrapper = data.get::<Wrapper>().unwrap();
+        let community = match wrapper.communities.get(&Scope::of(&msg)) {
+            Some(community) => community,
+            // no commands have been added in this guild
+            None => return,
+        };
+        let commands = &community.commands;
+
+        let words = commands::get_msg_words(&msg.content);
 
         // message is by definition non-empty
         let command = words[0];
 
-        // ignore if the message is not a command
-        if !command.starts_with('!') {
-            return;
-        }
-
-        // See if the command has been declared
-        match commands.get(&command) {
-            Some(x) => match x {
-                InternalCommand::Insert(_, _) => {
-                    let processor = &mut wrapper.processor;
-
-                    let words = get_msg_words(&msg.content);
-                    let args = match words.len() {
-                        1 => None,
-                        0 => panic!("does not happen"),
-                        _ => {
-                            let username = msg.author.name.clone();
-                            let index: usize;
-                            match words[1].parse::<usize>() {
-                                Ok(num) => index = num,
-                                Err(why) => {
-                                    let title =
-                                        String::from("Please enter a whole positive number");
-                                    let text = why.to_string();
-                                    let response = Response::new_error(title, text);
-                                    if let Err(why) =
-                                        send_message_embed(response, &msg, &ctx.http).await
-                                    {
-                                        println!("Error sending message: {:?}", why);
-                                    }
-                                    return;
-                                }
-                            }
-
-                            Some(Arguments::Insert(username, index))
-                        }
-                    };
-
-                    // run the command
-                    processor.process(&ctx, &msg, x, args).await;
-                }
-                InternalCommand::Remove(_, _) => {
-                    let processor = &mut wrapper.processor;
-                    let words = get_msg_words(&msg.content);
-
-                    let args = match words.len() {
-                        1 => None,
-                        0 => panic!("does not happen"),
-                        _ => {
-                            let index: usize;
-                            match words[1].parse::<usize>() {
-                                Ok(num) => index = num,
-                                Err(why) => {
-                                    let title =
-                                        String::from("Please enter a whole positive number");
-                                    let text = why.to_string();
-                                    let response = Response::new_error(title, text);
-                                    if let Err(why) =
-                                        send_message_embed(response, &msg, &ctx.http).await
-                                    {
-                                        println!("Error sending message: {:?}", why);
-                                    }
-                                    return;
-                                }
-                            }
-                            Some(Arguments::Remove(index))
-                        }
-                    };
-                    processor.process(&ctx, &msg, x, args).await;
+        // See if the command has been declared, do nothing when the command
+        // is not recognized
+        let internal_command = match commands.get(command) {
+            Some(internal_command) => internal_command.clone(),
+            None => return,
+        };
+        let roles = match &msg.member {
+            Some(member) => member.roles.as_slice(),
+            None => &[],
+        };
+        let captain = community.is_captain(internal_command.ids(), roles);
+        drop(data);
+
+        // players are shown by their nickname in the server, if they have one
+        let name = msg
+            .member
+            .as_ref()
+            .and_then(|member| member.nick.clone())
+            .unwrap_or_else(|| msg.author.name.clone());
+        let player = Player::new(msg.author.id.0, name);
+
+        // captains sign up other players by mentioning them after the map,
+        // unlike the author of a message replied to, who is mentioned too
+        let assignee = words
+            .iter()
+            .skip(2)
+            .find_map(|word| commands::parse_mention(word))
+            .and_then(|id| msg.mentions.iter().find(|user| user.id.0 == id))
+            .map(|user| Player::new(user.id.0, user.name.clone()));
+        let parsed = commands::parse_arguments(
+            &internal_command,
+            &words,
+            &player,
+            assignee.as_ref(),
+            captain,
+        );
+        let args = match parsed {
+            Ok(args) => args,
+            Err(why) => {
+                if let Err(why) = send_message_embed(why, msg.channel_id, &ctx.http).await {
+                    println!("Error sending message: {:?}", why);
                 }
-            },
-            None => {
-                // do nothing when the command is not recognized
+                return;
             }
-        }
+        };
+
+        let scope = Scope::of(&msg);
+        run_command(&ctx, scope, msg.channel_id, &internal_command, args).await;
+    }
+
+    /// Handles slash commands.
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        slash::handle(&ctx, interaction).await;
+    }
+
+    /// Signs the user up for the map of the reaction to a lineup embed.
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        handle_reaction(&ctx, reaction, true).await;
+    }
+
+    /// Withdraws the user from the map of the reaction to a lineup embed.
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        handle_reaction(&ctx, reaction, false).await;
     }
 
     // Set a handler to be called on the `ready` event. This is called when a
@@ -378,29 +408,61 @@ impl EventHandler for Handler {
 }
 
 #[group]
-#[commands(add_command, remove_command)]
+#[checks(Admin)]
+#[commands(
+    add_command,
+    remove_command,
+    clear_cache,
+    admin_role,
+    captain_role,
+    lineup_rules
+)]
 struct Admin;
 
 #[tokio::main]
 async fn main() {
+    let config = Config::from_env();
     let framework = StandardFramework::new()
         .configure(|c| c.prefix("!")) // set the bot's prefix to '!'
+        .on_dispatch_error(admin::dispatch_error)
         .group(&ADMIN_GROUP);
 
     // Login with a bot token from the environment
     let token = env::var("BOT_TOKEN").expect("Expected a token in the environment");
+    // slash commands are answered as the application of the bot
+    let application = Http::new_with_token(&token)
+        .get_current_application_info()
+        .await
+        .expect("Err getting the application of the bot");
     let mut client = Client::builder(&token)
+        .application_id(application.id.0)
         .event_handler(Handler)
         .framework(framework)
         .await
         .expect("Err creating client");
 
+    // restore the commands and bouts from before the last restart
+    let storage = Storage::new(&config.storage_path);
+    let wrapper = Wrapper::load(storage).expect("Err loading stored state");
+    let spire = Spire::new(&config);
+    let reminders = config.reminders.clone();
+    let poll_interval = config.poll_interval;
+
     // add context data structures
-    let wrapper = Wrapper::new();
     let mut data = client.data.write().await;
     data.insert::<Wrapper>(wrapper);
+    data.insert::<Spire>(Arc::new(spire));
+    data.insert::<Config>(Arc::new(config));
     drop(data);
 
+    // post reminders of upcoming bouts and no-shows in the background
+    let http = client.cache_and_http.http.clone();
+    tokio::spawn(post_reminders(client.data.clone(), http.clone(), reminders));
+    tokio::spawn(poll_bouts(client.data.clone(), http, poll_interval));
+
+    // replace the slash commands once, rather than on every reconnect
+    slash::register(&client.cache_and_http.http).await;
+
     // start listening for events by starting a single shard
     if let Err(why) = client.start().await {
         println!("An error occurred while running the client: {:?}", why);
@@ -412,173 +474,506 @@ async fn main() {
 async fn add_command(ctx: &Context, msg: &Message) -> CommandResult {
     let mut data = ctx.data.write().await;
     let wrapper = data.get_mut::<Wrapper>().unwrap();
-    let commands = &mut wrapper.commands;
+    let community = wrapper.community_mut(Scope::of(msg));
 
-    let words = get_msg_words(&msg.content);
+    let words = commands::get_msg_words(&msg.content);
+    let (new_command, internal_command) = match commands::parse_add_command(&words) {
+        Ok(parsed) => parsed,
+        Err(why) => return send_message_embed(why, msg.channel_id, &ctx.http).await,
+    };
 
-    if words.len() < 3 {
-        let title = String::from("Not enough identifier");
-        let text = String::from("Expected `!add_command <new_command> <action> [args]`");
+    let response = community.add_command(new_command, internal_command, msg.channel_id);
+    wrapper.save();
+
+    send_message_embed(response, msg.channel_id, &ctx.http).await
+}
+
+#[command]
+// Syntax: !remove_command <command>
+async fn remove_command(ctx: &Context, msg: &Message) -> CommandResult {
+    let mut data = ctx.data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let community = wrapper.community_mut(Scope::of(msg));
+
+    let words = commands::get_msg_words(&msg.content);
+
+    if words.len() < 2 {
+        let title = String::from("Not enough arguments");
+        let text = String::from("Usage: !remove_command <command_name>");
         let response = Response::new_error(title, text);
-        return send_message_embed(response, msg, &ctx.http).await;
+        return send_message_embed(response, msg.channel_id, &ctx.http).await;
     }
 
-    // prefix the new command with the identifier if the user hasn't done that
-    let new_command = if words[1].starts_with('!') {
-        String::from(words[1])
-    } else {
-        format!("!{}", words[1])
-    };
+    // prefix the command
+    let command = commands::prefixed(words[1]);
 
-    let action = words[2];
+    let response = community.remove_command(&command);
+    wrapper.save();
 
-    match action.to_lowercase().as_ref() {
-        "insert" => {
-            // !add_command <new_command> <action> <team_id> <tournament_id>
-            if words.len() < 5 {
-                let title = String::from("Not enough arguments");
-                let text = format!("Expected 5 arguments, received {}", words.len() - 1);
-                let response = Response::new_error(title, text);
-                return send_message_embed(response, msg, &ctx.http).await;
-            }
+    send_message_embed(response, msg.channel_id, &ctx.http).await
+}
 
-            let team_id: usize;
-            match words[3].parse::<usize>() {
-                Ok(num) => team_id = num,
-                Err(why) => {
-                    let title = String::from("Please enter a whole positive number");
-                    let text = why.to_string();
-                    let response = Response::new_error(title, text);
-                    return send_message_embed(response, msg, &ctx.http).await;
-                }
-            }
+#[command]
+// Syntax: !clear_cache
+async fn clear_cache(ctx: &Context, msg: &Message) -> CommandResult {
+    let data = ctx.data.read().await;
+    let spire = data.get::<Spire>().unwrap();
+    let cleared = spire.clear_cache();
+
+    let title = String::from("Cleared cache");
+    let text = format!(
+        "Dropped {} cached spire.gg responses, the next lookups use fresh data.",
+        cleared
+    );
+    let response = Response::new_success(title, text);
+    send_message_embed(response, msg.channel_id, &ctx.http).await
+}
 
-            let tournament_id: usize;
-            match words[4].parse::<usize>() {
-                Ok(num) => tournament_id = num,
-                Err(why) => {
-                    let title = String::from("Please enter a whole positive number");
-                    let text = why.to_string();
-                    let response = Response::new_error(title, text);
-                    return send_message_embed(response, msg, &ctx.http).await;
-                }
-            }
+#[command]
+// Syntax: !admin_role <role>
+async fn admin_role(ctx: &Context, msg: &Message) -> CommandResult {
+    let words = commands::get_msg_words(&msg.content);
+    if words.len() < 2 {
+        let title = String::from("Not enough arguments");
+        let text = String::from("Usage: !admin_role <role|none>");
+        let response = Response::new_error(title, text);
+        return send_message_embed(response, msg.channel_id, &ctx.http).await;
+    }
+    let role = match commands::parse_role(words[1]) {
+        Ok(role) => role.map(RoleId),
+        Err(why) => return send_message_embed(why, msg.channel_id, &ctx.http).await,
+    };
 
-            commands.add_command(
-                new_command.clone(),
-                InternalCommand::Insert(tournament_id, team_id),
+    let mut data = ctx.data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    wrapper.community_mut(Scope::of(msg)).admin_role = role;
+    wrapper.save();
+
+    let response = match role {
+        Some(role) => {
+            let text = format!(
+                "Members with the role <@&{}> can now manage the commands.",
+                role
             );
+            Response::new_success(String::from("Set admin role"), text)
         }
+        None => {
+            let text = String::from("Only server admins can manage the commands now.");
+            Response::new_success(String::from("Cleared admin role"), text)
+        }
+    };
+    send_message_embed(response, msg.channel_id, &ctx.http).await
+}
 
-        "remove" => {
-            if words.len() < 5 {
-                let title = String::from("Invalid command");
-                let text = format!("Expected 5 arguments, received {}", words.len() - 1);
-                let response = Response::new_error(title, text);
-                return send_message_embed(response, msg, &ctx.http).await;
-            }
-
-            let team_id: usize;
-            match words[3].parse::<usize>() {
-                Ok(num) => team_id = num,
-                Err(why) => {
-                    let title = String::from("Please enter a whole positive number");
-                    let text = why.to_string();
-                    let response = Response::new_error(title, text);
-                    return send_message_embed(response, msg, &ctx.http).await;
-                }
-            }
+#[command]
+// Syntax: !captain_role <command> <role>
+async fn captain_role(ctx: &Context, msg: &Message) -> CommandResult {
+    let words = commands::get_msg_words(&msg.content);
+    if words.len() < 3 {
+        let title = String::from("Not enough arguments");
+        let text = String::from("Usage: !captain_role <command> <role|none>");
+        let response = Response::new_error(title, text);
+        return send_message_embed(response, msg.channel_id, &ctx.http).await;
+    }
+    let role = match commands::parse_role(words[2]) {
+        Ok(role) => role.map(RoleId),
+        Err(why) => return send_message_embed(why, msg.channel_id, &ctx.http).await,
+    };
 
-            let tournament_id: usize;
-            match words[4].parse::<usize>() {
-                Ok(num) => tournament_id = num,
-                Err(why) => {
-                    let title = String::from("Please enter a whole positive number");
-                    let text = why.to_string();
-                    let response = Response::new_error(title, text);
-                    return send_message_embed(response, msg, &ctx.http).await;
-                }
-            }
+    let mut data = ctx.data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let community = wrapper.community_mut(Scope::of(msg));
+    let name = commands::prefixed(words[1]);
+    let ids = match community.commands.get(&name) {
+        Some(command) => command.ids(),
+        None => {
+            let text = format!("The command `{}` could not be found.", name);
+            let response = Response::new_warning(String::from("Command not found"), text);
+            return send_message_embed(response, msg.channel_id, &ctx.http).await;
+        }
+    };
 
-            commands.add_command(
-                new_command.clone(),
-                InternalCommand::Remove(tournament_id, team_id),
+    let response = match role {
+        Some(role) => {
+            community.captains.insert(ids, role);
+            let text = format!(
+                "Members with the role <@&{}> are now captains of the team of `{}`.",
+                role, name
             );
+            Response::new_success(String::from("Set captain role"), text)
         }
-
-        _ => {
-            let title = String::from("Invalid command");
-            let text = String::from("Expected: `!add_command <new_command> <action> [args].`\nInvalid action, please use one of `insert`, `remove`, or `poll`.");
-            let response = Response::new_error(title, text);
-            return send_message_embed(response, msg, &ctx.http).await;
+        None => {
+            community.captains.remove(&ids);
+            let text = format!("The team of `{}` has no captains now.", name);
+            Response::new_success(String::from("Cleared captain role"), text)
         }
+    };
+    wrapper.save();
+    send_message_embed(response, msg.channel_id, &ctx.http).await
+}
+
+#[command]
+// Syntax: !lineup_rules <command> [<rule> <value>]
+async fn lineup_rules(ctx: &Context, msg: &Message) -> CommandResult {
+    let words = commands::get_msg_words(&msg.content);
+    if words.len() != 2 && words.len() != 4 {
+        let title = String::from("Wrong number of arguments");
+        let text = String::from("Usage: !lineup_rules <command> [<rule> <value>]");
+        let response = Response::new_error(title, text);
+        return send_message_embed(response, msg.channel_id, &ctx.http).await;
     }
 
-    let title = String::from("Added command");
-    let text = format!("Sucessfully added command `{}`.", new_command);
-    let response = Response::new_success(title, text);
+    let mut data = ctx.data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let community = wrapper.community_mut(Scope::of(msg));
+    let name = commands::prefixed(words[1]);
+    let ids = match community.commands.get(&name) {
+        Some(command) => command.ids(),
+        None => {
+            let text = format!("The command `{}` could not be found.", name);
+            let response = Response::new_warning(String::from("Command not found"), text);
+            return send_message_embed(response, msg.channel_id, &ctx.http).await;
+        }
+    };
+
+    if words.len() == 4 {
+        if let Err(why) = community.processor.set_rule(ids, words[2], words[3]) {
+            return send_message_embed(why, msg.channel_id, &ctx.http).await;
+        }
+        wrapper.save();
+    }
 
-    send_message_embed(response, msg, &ctx.http).await
+    let rules = wrapper
+        .community_mut(Scope::of(msg))
+        .processor
+        .get_rules(ids);
+    let title = format!("Lineup rules of `{}`", name);
+    let response = Response::new_success(title, rules.get_description());
+    send_message_embed(response, msg.channel_id, &ctx.http).await
 }
 
-#[command]
-// Syntax: !remove_command <command>
-async fn remove_command(ctx: &Context, msg: &Message) -> CommandResult {
+/// Runs a team command in `scope` and sends the replies to `channel`, where
+/// the command was used.
+async fn run_command(
+    ctx: &Context,
+    scope: Scope,
+    channel: ChannelId,
+    command: &InternalCommand,
+    args: Option<Arguments>,
+) {
+    let data_read = ctx.data.read().await;
+    let spire = data_read.get::<Spire>().unwrap().clone();
+    let config = data_read.get::<Config>().unwrap().clone();
+    let tracked = match data_read.get::<Wrapper>().unwrap().communities.get(&scope) {
+        Some(community) => community.processor.get_tracked(command.ids()),
+        None => return,
+    };
+    drop(data_read);
+
+    // spire.gg may be slow, so don't keep the other commands waiting
+    let fetched = Processor::fetch(&spire, command, tracked).await;
+
     let mut data = ctx.data.write().await;
     let wrapper = data.get_mut::<Wrapper>().unwrap();
-    let commands = &mut wrapper.commands;
+    let community = match wrapper.communities.get_mut(&scope) {
+        Some(community) => community,
+        None => return,
+    };
 
-    let words = get_msg_words(&msg.content);
+    community.channels.insert(command.ids(), channel);
+    let processor = &mut community.processor;
+    let replies = processor.apply(&spire, command, args, fetched);
+    wrapper.save();
+    drop(data);
 
-    if words.len() < 2 {
-        let title = String::from("Not enough arguments");
-        let text = String::from("Usage: !remove_command <command_name>");
-        let response = Response::new_error(title, text);
-        return send_message_embed(response, msg, &ctx.http).await;
+    let pin = config.pin_lineups;
+    send_replies(replies, channel, &ctx.http, &spire, &ctx.data, scope, pin).await;
+}
+
+/// Gets the map number of a reaction in `MAP_EMOJIS`.
+fn map_index(emoji: &ReactionType) -> Option<usize> {
+    match emoji {
+        ReactionType::Unicode(emoji) => MAP_EMOJIS
+            .iter()
+            .position(|map| map == emoji)
+            .map(|i| i + 1),
+        _ => None,
     }
+}
 
-    // prefix the command
-    let command = if words[1].starts_with('!') {
-        String::from(words[1])
+/// Signs the user who reacted up for a map of a lineup embed if the reaction
+/// was `added`, otherwise withdraws them. Other reactions are ignored.
+async fn handle_reaction(ctx: &Context, reaction: Reaction, added: bool) {
+    let index = match map_index(&reaction.emoji) {
+        Some(index) => index,
+        None => return,
+    };
+    let user = match reaction.user(ctx).await {
+        Ok(user) if !user.bot => user,
+        // including the reactions the bot adds itself
+        Ok(_) => return,
+        Err(why) => {
+            println!("Error getting the user of a reaction: {:?}", why);
+            return;
+        }
+    };
+
+    // added reactions come with the member, so the nickname is known
+    // without another request
+    let nick = reaction
+        .member
+        .as_ref()
+        .and_then(|member| member.nick.clone());
+    let name = nick.unwrap_or(user.name);
+
+    let mut data = ctx.data.write().await;
+    let spire = data.get::<Spire>().unwrap().clone();
+    let config = data.get::<Config>().unwrap().clone();
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let scope = Scope::new(reaction.guild_id, reaction.channel_id);
+    let community = match wrapper.communities.get_mut(&scope) {
+        Some(community) => community,
+        None => return,
+    };
+    let lineup = community
+        .lineups
+        .iter()
+        .find(|(_, posted)| posted.message == reaction.message_id);
+    let bout_id = match lineup {
+        Some((bout_id, _)) => *bout_id,
+        None => return,
+    };
+
+    let processor = &mut community.processor;
+    let player = Player::new(user.id.0, name);
+    let replies = if added {
+        processor.claim(bout_id, index, player)
     } else {
-        format!("!{}", words[1])
+        processor.release(bout_id, index, &player)
     };
+    wrapper.save();
+    drop(data);
 
-    match commands.remove_command(&command) {
-        Some(internal_command) => {
-            match internal_command {
-                InternalCommand::Insert(team_id, tournament_id) => {
-                    let processor = &mut wrapper.processor;
-                    processor.drop_entry((team_id, tournament_id));
+    let pin = config.pin_lineups;
+    let channel = reaction.channel_id;
+    send_replies(replies, channel, &ctx.http, &spire, &ctx.data, scope, pin).await;
+}
+
+/// Posts the due reminders of the bouts of all communities, reports the
+/// players who didn't check in to the captains, and posts the results of
+/// finished bouts, checking every `REMINDER_INTERVAL`.
+async fn post_reminders(data: Arc<RwLock<TypeMap>>, http: Arc<Http>, offsets: Vec<Duration>) {
+    let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let mut data_write = data.write().await;
+        let spire = data_write.get::<Spire>().unwrap().clone();
+        let wrapper = data_write.get_mut::<Wrapper>().unwrap();
+        let mut due = Vec::new();
+        let mut no_shows = Vec::new();
+        for community in wrapper.communities.values_mut() {
+            for (id, reminder) in community.processor.due_reminders(Local::now(), &offsets) {
+                // bouts tracked before channels were remembered have nowhere to go
+                if let Some(channel) = community.channels.get(&id) {
+                    due.push((*channel, reminder));
                 }
-                _ => {}
             }
+            for (id, bout, players) in community.processor.due_no_shows(Local::now()) {
+                if let Some(channel) = community.channels.get(&id) {
+                    let captains = community.captains.get(&id).copied();
+                    no_shows.push((*channel, bout, players, captains));
+                }
+            }
+        }
+        if !due.is_empty() || !no_shows.is_empty() {
+            wrapper.save();
+        }
+        drop(data_write);
 
-            let text = format!("Succesfully removed command `{}`.", &command);
-            let response = Response::new_success(String::from("Removed command"), text);
-            return send_message_embed(response, msg, &ctx.http).await;
+        for (channel, reminder) in due {
+            if let Err(why) = send_reminder_embed(channel, &http, &reminder, &spire).await {
+                println!("Error sending reminder: {:?}", why);
+            }
         }
-        None => {
-            let text = format!("The command `{}` could not be found.", &command);
-            let response = Response::new_warning(String::from("Command not found"), text);
-            return send_message_embed(response, msg, &ctx.http).await;
+        for (channel, bout, players, captains) in no_shows {
+            if let Err(why) = send_no_show_embed(channel, &http, &bout, &players, captains).await {
+                println!("Error reporting no-shows: {:?}", why);
+            }
         }
+
+        post_results(&data, &http).await;
     }
 }
 
-/// Split message contents by `' '`.
-fn get_msg_words(contents: &str) -> Vec<&str> {
-    contents.split(" ").collect()
+/// Refreshes the tracked bouts that started within `RESULTS_WINDOW`, so
+/// their result is posted once they finished, even for teams without a
+/// `poll` command.
+async fn post_results(data: &RwLock<TypeMap>, http: &Http) {
+    let mut started = Vec::new();
+    let data_read = data.read().await;
+    for (scope, community) in &data_read.get::<Wrapper>().unwrap().communities {
+        for id in community.processor.started(Local::now(), RESULTS_WINDOW) {
+            if let Some(channel) = community.channels.get(&id) {
+                started.push((*scope, id, *channel));
+            }
+        }
+    }
+    drop(data_read);
+
+    for (scope, id, channel) in started {
+        refresh_team(data, http, scope, id, channel).await;
+    }
 }
 
-async fn send_message_embed(response: Response, msg: &Message, http: &Http) -> CommandResult {
+/// Refreshes the next bout of every team with a `poll` command every
+/// `period`, posting what changed in the channel of the team.
+async fn poll_bouts(data: Arc<RwLock<TypeMap>>, http: Arc<Http>, period: Duration) {
+    if period.as_secs() == 0 {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+
+        // collect the polled teams first, so the lock isn't held for all of them
+        let mut polled = Vec::new();
+        let data_read = data.read().await;
+        for (scope, community) in &data_read.get::<Wrapper>().unwrap().communities {
+            for action in community.commands.actions() {
+                if let InternalCommand::Poll(tournament_id, team_id) = action {
+                    let channel = community.channels.get(&action.ids());
+                    if let Some(channel) = channel {
+                        let team = (*scope, *tournament_id, *team_id, *channel);
+                        if !polled.contains(&team) {
+                            polled.push(team);
+                        }
+                    }
+                }
+            }
+        }
+        drop(data_read);
+
+        for (scope, tournament_id, team_id, channel) in polled {
+            refresh_team(&data, &http, scope, (tournament_id, team_id), channel).await;
+        }
+    }
+}
+
+/// Refreshes the tracked bout of the team with `id` in the community of
+/// `scope`, posting what changed in `channel`.
+async fn refresh_team(
+    data: &RwLock<TypeMap>,
+    http: &Http,
+    scope: Scope,
+    id: (usize, usize),
+    channel: ChannelId,
+) {
+    let data_read = data.read().await;
+    let spire = data_read.get::<Spire>().unwrap().clone();
+    let config = data_read.get::<Config>().unwrap().clone();
+    let tracked = match data_read.get::<Wrapper>().unwrap().communities.get(&scope) {
+        Some(community) => community.processor.get_tracked(id),
+        None => return,
+    };
+    drop(data_read);
+
+    let (next_bout, outcome) = match Processor::fetch_next_bout(&spire, id, tracked).await {
+        Ok(fetched) => fetched,
+        Err(why) => {
+            println!("Error refreshing team {}: {}", id.1, why);
+            return;
+        }
+    };
+
+    let mut data_write = data.write().await;
+    let wrapper = data_write.get_mut::<Wrapper>().unwrap();
+    let community = match wrapper.communities.get_mut(&scope) {
+        Some(community) => community,
+        None => return,
+    };
+    let replies = community.processor.poll(id, next_bout, outcome);
+    if replies.is_empty() {
+        return;
+    }
+    wrapper.save();
+    drop(data_write);
+
+    let pin = config.pin_lineups;
+    send_replies(replies, channel, http, &spire, data, scope, pin).await;
+}
+
+/// Sends the replies of a processed command, in order, without holding on
+/// to `data` meanwhile. Lineups replace the lineup embed of their bout in the
+/// community of `scope`, see `send_bout_embed`.
+async fn send_replies(
+    replies: Vec<Reply>,
+    channel: ChannelId,
+    http: &Http,
+    spire: &Spire,
+    data: &RwLock<TypeMap>,
+    scope: Scope,
+    pin: bool,
+) {
+    let data_read = data.read().await;
+    let known = match data_read.get::<Wrapper>().unwrap().communities.get(&scope) {
+        Some(community) => community.lineups.clone(),
+        None => return,
+    };
+    drop(data_read);
+    let mut lineups = known.clone();
+
+    for reply in replies {
+        let status = match reply {
+            Reply::Message(response) => send_message_embed(response, channel, http).await,
+            Reply::Lineup(bout) => {
+                send_bout_embed(channel, http, &bout, spire, &mut lineups, pin).await
+            }
+            Reply::Schedule(schedule) => send_schedule_embed(channel, http, &schedule, spire).await,
+            Reply::Outcome(outcome) => send_outcome_embed(channel, http, &outcome, spire).await,
+            Reply::Standings(standings) => send_standings_embed(channel, http, &standings).await,
+            Reply::Scouting(scouting) => send_scouting_embed(channel, http, &scouting).await,
+        };
+        if let Err(why) = status {
+            println!("Error sending message: {:?}", why);
+        }
+    }
+
+    // remember the newly posted embeds
+    let mut data = data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let community = match wrapper.communities.get_mut(&scope) {
+        Some(community) => community,
+        None => return,
+    };
+    for (bout_id, message) in lineups {
+        if known.get(&bout_id) != Some(&message) {
+            community.lineups.insert(bout_id, message);
+        }
+    }
+    let pruned = community.prune_lineups();
+    wrapper.save();
+    drop(data);
+
+    // the lineups of bouts that are no longer tracked stay as they were
+    for posted in pruned {
+        if pin {
+            if let Err(why) = posted.channel.unpin(http, posted.message).await {
+                println!("Error unpinning lineup: {:?}", why);
+            }
+        }
+    }
+}
+
+async fn send_message_embed(response: Response, channel: ChannelId, http: &Http) -> CommandResult {
     let color = match response.response_type {
         ResponseType::Error => Colour::RED,
         ResponseType::Success => Colour::DARK_GREEN,
         ResponseType::Warning => Colour::ORANGE,
     };
 
-    msg.channel_id
+    channel
         .send_message(http, |m| {
             m.embed(|e| {
                 e.title(response.title);
@@ -592,14 +987,132 @@ async fn send_message_embed(response: Response, msg: &Message, http: &Http) -> C
     Ok(())
 }
 
-/// Generate an embed of the bout to send to the user(s).
-async fn send_bout_embed(msg: &Message, http: &Http, bout: &Bout) -> CommandResult {
-    msg.channel_id
+/// Shows the lineup of the bout. The lineup embed of the bout in `lineups`
+/// is edited if it was posted in `channel`, otherwise a new embed is posted
+/// and remembered, pinned if `pin` is set.
+async fn send_bout_embed(
+    channel: ChannelId,
+    http: &Http,
+    bout: &Bout,
+    spire: &Spire,
+    lineups: &mut HashMap<usize, LineupMessage>,
+    pin: bool,
+) -> CommandResult {
+    if let Some(posted) = lineups.get(&bout.get_id()) {
+        if posted.channel == channel {
+            let edited = channel
+                .edit_message(http, posted.message, |m| {
+                    m.embed(|e| bout_embed(e, bout, spire))
+                })
+                .await;
+            match edited {
+                Ok(_) => return Ok(()),
+                // e.g. the embed was deleted, so post a new one
+                Err(why) => println!("Error editing lineup: {:?}", why),
+            }
+        }
+    }
+
+    let message = channel
+        .send_message(http, |m| m.embed(|e| bout_embed(e, bout, spire)))
+        .await?;
+    if pin {
+        if let Some(previous) = lineups.get(&bout.get_id()) {
+            if let Err(why) = previous.channel.unpin(http, previous.message).await {
+                println!("Error unpinning lineup: {:?}", why);
+            }
+        }
+        if let Err(why) = channel.pin(http, message.id).await {
+            println!("Error pinning lineup: {:?}", why);
+        }
+    }
+
+    let posted = LineupMessage {
+        channel,
+        message: message.id,
+    };
+    lineups.insert(bout.get_id(), posted);
+
+    // players sign up by reacting with the number of a map
+    for emoji in MAP_EMOJIS.iter().take(bout.assignable_maps()) {
+        let reaction = ReactionType::Unicode(String::from(*emoji));
+        channel.create_reaction(http, message.id, reaction).await?;
+    }
+    Ok(())
+}
+
+/// Fills an embed with the lineup of the bout.
+fn bout_embed<'a>(e: &'a mut CreateEmbed, bout: &Bout, spire: &Spire) -> &'a mut CreateEmbed {
+    e.title(bout.get_title());
+    e.description(bout.get_description(spire.site_url()));
+    e.field("Maps", bout.get_maps(), false);
+    e.field("Bench", bout.get_bench(), false);
+    if let Some(checkin) = bout.get_checkin(Local::now()) {
+        e.field("Check-in", checkin, false);
+    }
+    e.color(Colour::BLITZ_BLUE);
+    e
+}
+
+/// Generate an embed of a page of the schedule.
+async fn send_schedule_embed(
+    channel: ChannelId,
+    http: &Http,
+    schedule: &Schedule,
+    spire: &Spire,
+) -> CommandResult {
+    channel
         .send_message(http, |m| {
             m.embed(|e| {
-                e.title(bout.get_title());
-                e.description(bout.get_description());
-                e.field("Maps", bout.get_maps(), false);
+                e.title(schedule.get_title());
+                e.description(schedule.get_description());
+                for bout in schedule.get_bouts() {
+                    e.field(bout.get_title(), bout.get_summary(spire.site_url()), false);
+                }
+                e.footer(|f| f.text(schedule.get_footer()));
+                e.color(Colour::BLITZ_BLUE);
+                e
+            });
+            m
+        })
+        .await?;
+    Ok(())
+}
+
+/// Generate an embed of the result of a finished bout.
+async fn send_outcome_embed(
+    channel: ChannelId,
+    http: &Http,
+    outcome: &Outcome,
+    spire: &Spire,
+) -> CommandResult {
+    channel
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title(outcome.get_title());
+                e.description(outcome.get_description(spire.site_url()));
+                e.field("Maps", outcome.get_maps(), false);
+                e.color(Colour::GOLD);
+                e
+            });
+            m
+        })
+        .await?;
+    Ok(())
+}
+
+/// Generate an embed of the standings of a group or bracket.
+async fn send_standings_embed(
+    channel: ChannelId,
+    http: &Http,
+    standings: &Standings,
+) -> CommandResult {
+    channel
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title(standings.get_title());
+                e.description(standings.get_description());
+                e.field("Table", standings.get_table(), false);
                 e.color(Colour::BLITZ_BLUE);
                 e
             });
@@ -608,3 +1121,92 @@ async fn send_bout_embed(msg: &Message, http: &Http, bout: &Bout) -> CommandResu
         .await?;
     Ok(())
 }
+
+/// Generate an embed of the recent form of an opposing team.
+async fn send_scouting_embed(
+    channel: ChannelId,
+    http: &Http,
+    scouting: &Scouting,
+) -> CommandResult {
+    channel
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title(scouting.get_title());
+                e.description(scouting.get_description());
+                e.field("Recent matches", scouting.get_bouts(), false);
+                e.field("Maps", scouting.get_maps(), false);
+                e.field("Players", scouting.get_roster(), false);
+                e.color(Colour::DARK_RED);
+                e
+            });
+            m
+        })
+        .await?;
+    Ok(())
+}
+
+/// Generate an embed reminding the team of a bout that is about to start.
+async fn send_reminder_embed(
+    channel: ChannelId,
+    http: &Http,
+    reminder: &Reminder,
+    spire: &Spire,
+) -> CommandResult {
+    let bout = reminder.get_bout();
+    channel
+        .send_message(http, |m| {
+            if let Some(mentions) = reminder.get_mentions() {
+                m.content(mentions);
+            }
+            m.embed(|e| {
+                e.title(reminder.get_title());
+                e.description(bout.get_description(spire.site_url()));
+                e.field("Players", reminder.get_players(), false);
+                e.field("Maps", bout.get_maps(), false);
+                match reminder.get_warning() {
+                    Some(warning) => {
+                        e.field("Warning", warning, false);
+                        e.color(Colour::ORANGE);
+                    }
+                    None => {
+                        e.color(Colour::BLITZ_BLUE);
+                    }
+                }
+                e
+            });
+            m
+        })
+        .await?;
+    Ok(())
+}
+
+/// Generate an embed telling the captains which players of a bout that just
+/// started didn't check in. The captains are pinged if the team has a
+/// captain role.
+async fn send_no_show_embed(
+    channel: ChannelId,
+    http: &Http,
+    bout: &Bout,
+    players: &[Player],
+    captains: Option<RoleId>,
+) -> CommandResult {
+    let players: Vec<_> = players.iter().map(Player::mention).collect();
+    channel
+        .send_message(http, |m| {
+            if let Some(captains) = captains {
+                m.content(format!("<@&{}>", captains));
+            }
+            m.embed(|e| {
+                e.title(format!("No-shows: {}", bout.get_title()));
+                e.description(format!(
+                    "The match has started, but {} didn't check in.",
+                    players.join(", ")
+                ));
+                e.color(Colour::RED);
+                e
+            });
+            m
+        })
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! A fake spire.gg API for tests. It serves canned JSON responses over plain
+//! HTTP on a random local port, so the API layer and the command flow can be
+//! exercised without network access.
+
+use crate::config::Config;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::sleep;
+
+/// Directory containing recorded spire.gg responses.
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+/// A canned HTTP response.
+#[derive(Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    delay: Duration,
+}
+
+impl MockResponse {
+    /// Constructs a response with the given status code and JSON body.
+    pub fn new(status: u16, body: &str) -> MockResponse {
+        MockResponse {
+            status,
+            headers: Vec::new(),
+            body: String::from(body),
+            delay: Duration::from_secs(0),
+        }
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// Waits for `delay` before answering.
+    pub fn delay(mut self, delay: Duration) -> MockResponse {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Routes and received requests, shared with the server task.
+#[derive(Default)]
+struct State {
+    routes: HashMap<String, MockResponse>,
+    queued: HashMap<String, VecDeque<MockResponse>>,
+    requests: Vec<String>,
+}
+
+/// Handle to a running fake spire.gg server.
+pub struct MockSpire {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockSpire {
+    /// Starts a server without any routes. Unknown routes answer with a 404.
+    pub async fn start() -> MockSpire {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, shared.clone()));
+            }
+        });
+
+        MockSpire { url, state }
+    }
+
+    /// Configuration pointing the bot at this server, with short delays so
+    /// tests of retries stay fast. Caching is disabled, so every lookup
+    /// reaches the server.
+    pub fn config(&self) -> Config {
+        Config {
+            api_url: self.url.clone(),
+            site_url: String::from("https://spire.test"),
+            timeout: Duration::from_secs(2),
+            backoff: Duration::from_millis(10),
+            cache_ttl: Duration::from_secs(0),
+            ..Config::default()
+        }
+    }
+
+    /// Answers requests for `path` (including the query) with `body`.
+    pub fn route(&self, path: &str, status: u16, body: &str) {
+        self.respond(path, MockResponse::new(status, body));
+    }
+
+    /// Answers requests for `path` with `response`, once the responses
+    /// queued by `respond_once` are used up.
+    pub fn respond(&self, path: &str, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.routes.insert(String::from(path), response);
+    }
+
+    /// Answers the next request for `path` with `response`.
+    pub fn respond_once(&self, path: &str, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        let queue = state.queued.entry(String::from(path)).or_default();
+        queue.push_back(response);
+    }
+
+    /// Answers requests for `path` with the recorded response in
+    /// `fixtures/<fixture>`.
+    pub fn fixture(&self, path: &str, fixture: &str) {
+        self.route(path, 200, &read_fixture(fixture));
+    }
+
+    /// The paths of all requests received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// The number of requests received for `path`.
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests().iter().filter(|p| *p == path).count()
+    }
+}
+
+/// Reads the recorded response in `fixtures/<fixture>`, e.g. to serve an
+/// altered copy of it.
+pub fn read_fixture(fixture: &str) -> String {
+    fs::read_to_string(Path::new(FIXTURES).join(fixture)).unwrap()
+}
+
+/// Reads the recorded response in `fixtures/<fixture>` and alters its JSON
+/// with `edit`.
+pub fn edit_fixture<F: FnOnce(&mut Value)>(fixture: &str, edit: F) -> String {
+    let mut json: Value = serde_json::from_str(&read_fixture(fixture)).unwrap();
+    edit(&mut json);
+    json.to_string()
+}
+
+/// Answers a single HTTP request.
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    // the requests of reqwest are small, so reading the head is enough
+    let mut buffer = vec![0; 8192];
+    let mut read = 0;
+    while !buffer[..read].windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buffer[read..]).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => read += n,
+        }
+    }
+
+    let head = String::from_utf8_lossy(&buffer[..read]);
+    let path = head.split(' ').nth(1).unwrap_or("/").to_string();
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(path.clone());
+        let queued = state.queued.get_mut(&path).and_then(VecDeque::pop_front);
+        queued.or_else(|| state.routes.get(&path).cloned())
+    };
+    let response = response.unwrap_or_else(|| MockResponse::new(404, "{\"code\":\"NOT_FOUND\"}"));
+    sleep(response.delay).await;
+
+    let mut raw = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        raw.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    raw.push_str("\r\n");
+    raw.push_str(&response.body);
+    let _ = stream.write_all(raw.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
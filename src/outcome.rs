@@ -0,0 +1,142 @@
+use crate::bout::{Bout, MapName, Player};
+
+/// A team in a bout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Home,
+    Away,
+}
+
+/// The final result of a finished bout.
+#[derive(Debug)]
+pub struct Outcome {
+    id: usize,
+    home: String,
+    away: String,
+
+    /// The team spire.gg declares the winner, e.g. after a forfeit.
+    winner: Option<Side>,
+
+    /// Maps won by the home and away team.
+    score: (usize, usize),
+
+    /// The maps with the team that won them, `None` if a map wasn't played.
+    maps: Vec<(MapName, Option<Side>)>,
+
+    /// The players of our team per map, taken from the tracked lineup.
+    players: Vec<Vec<Player>>,
+}
+
+impl Outcome {
+    /// Constructs the outcome of a bout. Without a `score`, the score is
+    /// counted from the winners of the maps.
+    pub fn new(
+        id: usize,
+        home: String,
+        away: String,
+        winner: Option<Side>,
+        score: Option<(usize, usize)>,
+        maps: Vec<(MapName, Option<Side>)>,
+    ) -> Outcome {
+        let score = score.unwrap_or_else(|| {
+            let won = |side| maps.iter().filter(|(_, w)| *w == Some(side)).count();
+            (won(Side::Home), won(Side::Away))
+        });
+        let players = vec![Vec::new(); maps.len()];
+
+        Outcome {
+            id,
+            home,
+            away,
+            winner,
+            score,
+            maps,
+            players,
+        }
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_home(&self) -> &str {
+        &self.home
+    }
+
+    pub fn get_away(&self) -> &str {
+        &self.away
+    }
+
+    /// The maps with the team that won them.
+    pub fn get_map_winners(&self) -> &[(MapName, Option<Side>)] {
+        &self.maps
+    }
+
+    /// Adds the players of the lineup of `bout`, matched by map name.
+    pub fn set_lineup(&mut self, bout: &Bout) {
+        for (i, (map, _)) in self.maps.iter().enumerate() {
+            self.players[i] = bout.get_players_of(map).into_iter().cloned().collect();
+        }
+    }
+
+    /// Maps won by the home and away team.
+    pub fn get_score(&self) -> (usize, usize) {
+        self.score
+    }
+
+    /// The side that won, `None` on a draw. Without a declared winner, the
+    /// team that won the most maps wins.
+    pub fn get_winning_side(&self) -> Option<Side> {
+        let (home, away) = self.score;
+        self.winner.or(if home > away {
+            Some(Side::Home)
+        } else if away > home {
+            Some(Side::Away)
+        } else {
+            None
+        })
+    }
+
+    /// The team that won, `None` on a draw.
+    pub fn get_winner(&self) -> Option<&str> {
+        match self.get_winning_side()? {
+            Side::Home => Some(&self.home),
+            Side::Away => Some(&self.away),
+        }
+    }
+
+    pub fn get_title(&self) -> String {
+        let (home, away) = self.score;
+        format!("{} {} - {} {}", &self.home, home, away, &self.away)
+    }
+
+    pub fn get_description(&self, site_url: &str) -> String {
+        let winner = match self.get_winner() {
+            Some(team) => format!("Winner: {}", team),
+            None => String::from("Draw"),
+        };
+        format!("{}\n{}/match/{}", winner, site_url, self.id)
+    }
+
+    pub fn get_maps(&self) -> String {
+        let mut result = String::new();
+        for ((map, winner), players) in self.maps.iter().zip(&self.players) {
+            let winner = match winner {
+                Some(Side::Home) => &self.home,
+                Some(Side::Away) => &self.away,
+                None => {
+                    result.push_str(&format!("{}: not played\n", map));
+                    continue;
+                }
+            };
+            let players = if players.is_empty() {
+                String::from("nobody signed up")
+            } else {
+                let mentions: Vec<_> = players.iter().map(Player::mention).collect();
+                mentions.join(", ")
+            };
+            result.push_str(&format!("{}: won by {} ({})\n", map, winner, players));
+        }
+        result
+    }
+}
@@ -0,0 +1,940 @@
+use crate::api::{ApiError, Spire};
+use crate::bout::{Bout, Player};
+use crate::commands::{self, Arguments, InternalCommand};
+use crate::outcome::Outcome;
+use crate::reminder::{self, Reminder};
+use crate::response::Response;
+use crate::rules::Rules;
+use crate::schedule::Schedule;
+use crate::scouting::Scouting;
+use crate::standings::Standings;
+use crate::storage;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Number of finished bouts kept per tournament per team.
+const ARCHIVE_SIZE: usize = 10;
+
+/// A message the bot sends in response to a command.
+#[derive(Debug)]
+pub enum Reply {
+    /// A plain embed, e.g. an error.
+    Message(Response),
+
+    /// The current lineup of a bout.
+    Lineup(Bout),
+
+    /// A page of the remaining bouts of a team.
+    Schedule(Schedule),
+
+    /// The result of a finished bout.
+    Outcome(Outcome),
+
+    /// The standings of the group or bracket of a team.
+    Standings(Standings),
+
+    /// The recent form of an opposing team.
+    Scouting(Scouting),
+}
+
+/// What a command needs from spire.gg, see `Processor::fetch`.
+#[derive(Debug)]
+pub enum Fetched {
+    /// The command only uses the tracked bout.
+    Nothing,
+
+    /// The next bout of a team, with the outcome of the bout tracked until
+    /// now if it was played.
+    NextBout(Box<Bout>, Option<Outcome>),
+
+    /// The last finished bout of a team.
+    Outcome(Outcome),
+
+    /// The remaining bouts of a team.
+    Schedule(Vec<Bout>),
+
+    /// The standings of a tournament.
+    Standings(Standings),
+
+    /// The recent form of an opposing team.
+    Scouting(Scouting),
+}
+
+/// Data structure to keep track of all the active bouts per tournament per
+/// team.
+#[derive(Serialize, Deserialize)]
+pub struct Processor {
+    #[serde(with = "storage::pairs")]
+    bouts: HashMap<(usize, usize), Bout>,
+
+    /// Final lineups of the previously tracked bouts, oldest first.
+    #[serde(with = "storage::pairs", default)]
+    archive: HashMap<(usize, usize), Vec<Bout>>,
+
+    /// Lineup rules per tournament per team, if they differ from the
+    /// default rules.
+    #[serde(with = "storage::pairs", default)]
+    rules: HashMap<(usize, usize), Rules>,
+}
+
+impl Processor {
+    pub fn new() -> Processor {
+        Processor {
+            bouts: HashMap::new(),
+            archive: HashMap::new(),
+            rules: HashMap::new(),
+        }
+    }
+
+    /// Gets the lineup rules of a team.
+    pub fn get_rules(&self, id: (usize, usize)) -> Rules {
+        self.rules.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Changes a lineup rule of a team, see `Rules::set`. The new team size
+    /// and check-in window apply to the tracked bout right away.
+    pub fn set_rule(
+        &mut self,
+        id: (usize, usize),
+        rule: &str,
+        value: &str,
+    ) -> Result<(), Response> {
+        let rules = self.rules.entry(id).or_default();
+        rules.set(rule, value)?;
+        if let Some(bout) = self.bouts.get_mut(&id) {
+            bout.set_team_size(rules.team_size);
+            bout.set_checkin(rules.get_checkin_window());
+        }
+        Ok(())
+    }
+
+    /// Fetches what `command` needs from spire.gg, given the id of the bout
+    /// `tracked` for the team. Needs no access to the processor, so slow
+    /// requests don't hold up the commands of other teams.
+    pub async fn fetch(
+        spire: &Spire,
+        command: &InternalCommand,
+        tracked: Option<usize>,
+    ) -> Result<Fetched, ApiError> {
+        match command {
+            InternalCommand::Remove(_, _) => Ok(Fetched::Nothing),
+            InternalCommand::Insert(tournament_id, team_id)
+            | InternalCommand::Bench(tournament_id, team_id)
+            | InternalCommand::CheckIn(tournament_id, team_id)
+            | InternalCommand::Poll(tournament_id, team_id) => {
+                let id = (*tournament_id, *team_id);
+                let (next_bout, outcome) = Processor::fetch_next_bout(spire, id, tracked).await?;
+                Ok(Fetched::NextBout(Box::new(next_bout), outcome))
+            }
+            InternalCommand::Schedule(tournament_id, team_id) => {
+                let bouts = spire.get_schedule(*tournament_id, *team_id).await?;
+                Ok(Fetched::Schedule(bouts))
+            }
+            InternalCommand::Results(tournament_id, team_id) => {
+                let outcome = spire.find_last_outcome(*tournament_id, *team_id).await?;
+                Ok(Fetched::Outcome(outcome))
+            }
+            InternalCommand::Standings(tournament_id, team_id) => {
+                let standings = spire.get_standings(*tournament_id, *team_id).await?;
+                Ok(Fetched::Standings(standings))
+            }
+            InternalCommand::Scout(tournament_id, team_id) => {
+                let scouting = spire
+                    .scout_opponent(*tournament_id, *team_id, tracked)
+                    .await?;
+                Ok(Fetched::Scouting(scouting))
+            }
+        }
+    }
+
+    /// Fetches the next bout of a team, with the outcome of the bout
+    /// `tracked` until now if that is no longer the next bout.
+    pub async fn fetch_next_bout(
+        spire: &Spire,
+        id: (usize, usize),
+        tracked: Option<usize>,
+    ) -> Result<(Bout, Option<Outcome>), ApiError> {
+        let next_bout = spire.find_next_bout(id.0, id.1).await?;
+        let outcome = match tracked {
+            // the previous match may have been played
+            Some(bout_id) if bout_id != next_bout.get_id() => spire.get_outcome(bout_id).await.ok(),
+            _ => None,
+        };
+        Ok((next_bout, outcome))
+    }
+
+    /// Handles the command with what was `fetched` for it, see `fetch`, and
+    /// updates internal state if necessary. Returns the messages to send
+    /// back to the user.
+    pub fn apply(
+        &mut self,
+        spire: &Spire,
+        command: &InternalCommand,
+        args: Option<Arguments>,
+        fetched: Result<Fetched, ApiError>,
+    ) -> Vec<Reply> {
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(why) => return vec![Reply::Message(spire.error_response(&why))],
+        };
+
+        match (command, fetched) {
+            (InternalCommand::Remove(tournament_id, team_id), _) => {
+                self.remove(spire, *tournament_id, *team_id, args)
+            }
+            (InternalCommand::Bench(tournament_id, team_id), Fetched::NextBout(next, outcome)) => {
+                self.bench((*tournament_id, *team_id), *next, outcome, args)
+            }
+            (
+                InternalCommand::CheckIn(tournament_id, team_id),
+                Fetched::NextBout(next, outcome),
+            ) => self.check_in((*tournament_id, *team_id), *next, outcome, args),
+            (InternalCommand::Insert(tournament_id, team_id), Fetched::NextBout(next, outcome)) => {
+                self.insert((*tournament_id, *team_id), *next, outcome, args)
+            }
+            (InternalCommand::Poll(tournament_id, team_id), Fetched::NextBout(next, outcome)) => {
+                let replies = self.poll((*tournament_id, *team_id), *next, outcome);
+                if replies.is_empty() {
+                    let title = String::from("No changes");
+                    let text = String::from("The next match is unchanged on spire.gg.");
+                    return vec![Reply::Message(Response::new_success(title, text))];
+                }
+                replies
+            }
+            (InternalCommand::Schedule(_, _), Fetched::Schedule(bouts)) => {
+                Processor::schedule(bouts, args)
+            }
+            (InternalCommand::Results(tournament_id, team_id), Fetched::Outcome(outcome)) => {
+                self.results((*tournament_id, *team_id), outcome)
+            }
+            (InternalCommand::Standings(_, _), Fetched::Standings(standings)) => {
+                vec![Reply::Standings(standings)]
+            }
+            (InternalCommand::Scout(_, _), Fetched::Scouting(scouting)) => {
+                vec![Reply::Scouting(scouting)]
+            }
+            // fetched for another command
+            _ => Vec::new(),
+        }
+    }
+
+    /// Gets the id of the bout tracked for a team.
+    pub fn get_tracked(&self, id: (usize, usize)) -> Option<usize> {
+        self.bouts.get(&id).map(|bout| bout.get_id())
+    }
+
+    /// Whether the bout with `bout_id` is tracked for any team.
+    pub fn is_tracked(&self, bout_id: usize) -> bool {
+        self.bouts.values().any(|bout| bout.get_id() == bout_id)
+    }
+
+    pub fn drop_entry(&mut self, id: (usize, usize)) -> Option<Bout> {
+        self.archive.remove(&id);
+        self.bouts.remove(&id)
+    }
+
+    /// Collects the reminders of the tracked bouts that are due at `now`,
+    /// and marks them as sent. See `reminder::due_offset`.
+    pub fn due_reminders(
+        &mut self,
+        now: DateTime<Local>,
+        offsets: &[Duration],
+    ) -> Vec<((usize, usize), Reminder)> {
+        let mut reminders = Vec::new();
+        for (id, bout) in self.bouts.iter_mut() {
+            if let Some(offset) = reminder::due_offset(bout, now, offsets) {
+                bout.set_reminded(offset);
+                reminders.push((*id, Reminder::new(bout.clone(), now)));
+            }
+        }
+        reminders
+    }
+
+    /// Collects the players of the tracked bouts that started without
+    /// checking in, see `Bout::take_no_shows`.
+    pub fn due_no_shows(
+        &mut self,
+        now: DateTime<Local>,
+    ) -> Vec<((usize, usize), Bout, Vec<Player>)> {
+        let mut due = Vec::new();
+        for (id, bout) in self.bouts.iter_mut() {
+            let no_shows = bout.take_no_shows(now);
+            if !no_shows.is_empty() {
+                due.push((*id, bout.clone(), no_shows));
+            }
+        }
+        due
+    }
+
+    /// The teams whose tracked bout started at most `window` before `now`,
+    /// so it may have finished since.
+    pub fn started(&self, now: DateTime<Local>, window: Duration) -> Vec<(usize, usize)> {
+        self.bouts
+            .iter()
+            .filter(
+                |(_, bout)| match now.signed_duration_since(bout.get_datetime()).to_std() {
+                    Ok(since) => since <= window,
+                    Err(_) => false,
+                },
+            )
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Stores the final lineup of a bout that is no longer tracked.
+    fn archive_bout(&mut self, id: (usize, usize), bout: Bout) {
+        let archived = self.archive.entry(id).or_default();
+        archived.push(bout);
+        if archived.len() > ARCHIVE_SIZE {
+            archived.remove(0);
+        }
+    }
+
+    /// Removes a player from a bout, identified by `tournament_id` and
+    /// `team_id`, at a specified index. Requires `args` to be
+    /// `Some(Arguments::Remove(index, player))`, where a `player` can only
+    /// remove themselves. In case `args` is incorrect, reply with an
+    /// appropriate error.
+    fn remove(
+        &mut self,
+        spire: &Spire,
+        tournament_id: usize,
+        team_id: usize,
+        args: Option<Arguments>,
+    ) -> Vec<Reply> {
+        let (index, slot, player) = match args {
+            Some(Arguments::Remove(index, slot, player)) => (index, slot, player),
+            _ => {
+                let title = String::from("Missing map number");
+                let text = String::from("Please specify which map to remove a player from");
+                return vec![Reply::Message(Response::new_error(title, text))];
+            }
+        };
+
+        match self.bouts.get_mut(&(tournament_id, team_id)) {
+            Some(bout) => {
+                let mut replies = Vec::new();
+                let slot = match &player {
+                    // a player without a slot on the map can only clear an
+                    // open slot
+                    Some(player) => {
+                        let slot = slot.or_else(|| bout.find_slot(index, player)).unwrap_or(1);
+                        let claimed = bout.get_player_at(index, slot);
+                        if matches!(claimed, Some(claimed) if claimed != player) {
+                            let why = commands::not_captain();
+                            return vec![Reply::Message(why), Reply::Lineup(bout.clone())];
+                        }
+                        Some(slot)
+                    }
+                    None => slot,
+                };
+                match bout.remove_player(index, slot) {
+                    Ok(()) => replies.extend(promotion(bout, index)),
+                    Err(why) => replies.push(Reply::Message(why)),
+                }
+                replies.push(Reply::Lineup(bout.clone()));
+                replies
+            }
+            None => {
+                let why = ApiError::NoMatches {
+                    tournament_id,
+                    team_id,
+                };
+                vec![Reply::Message(spire.error_response(&why))]
+            }
+        }
+    }
+
+    /// Inserts a player into the bout of a team, identified by `id`, at a
+    /// specified index once the bout is refreshed to `next_bout`. Requires
+    /// `args` to be `Some(Arguments::Insert(player, index))`. Without
+    /// arguments, only the lineup is shown.
+    fn insert(
+        &mut self,
+        id: (usize, usize),
+        next_bout: Bout,
+        outcome: Option<Outcome>,
+        args: Option<Arguments>,
+    ) -> Vec<Reply> {
+        let rules = self.get_rules(id);
+
+        // first update the bout / insert a new bout
+        let (bout, mut replies) = self.refresh(id, next_bout, outcome);
+
+        if let Some(Arguments::Insert(player, index, slot, replaces)) = args {
+            if let Err(why) = bout.insert_player(index, slot, player, replaces, &rules) {
+                replies.push(Reply::Message(why));
+            }
+        }
+
+        replies.push(Reply::Lineup(bout.clone()));
+        replies
+    }
+
+    /// Adds a player to or removes them from the bench of the bout of a
+    /// team, identified by `id`, once refreshed to `next_bout`. Requires
+    /// `args` to be `Some(Arguments::Bench(player, joins))`.
+    fn bench(
+        &mut self,
+        id: (usize, usize),
+        next_bout: Bout,
+        outcome: Option<Outcome>,
+        args: Option<Arguments>,
+    ) -> Vec<Reply> {
+        let (bout, mut replies) = self.refresh(id, next_bout, outcome);
+
+        match args {
+            Some(Arguments::Bench(player, true)) => bout.join_bench(player),
+            Some(Arguments::Bench(player, false)) if !bout.leave_bench(&player) => {
+                let title = String::from("Not on the bench");
+                let text = format!("{} isn't on the bench.", player.mention());
+                replies.push(Reply::Message(Response::new_warning(title, text)));
+            }
+            _ => {}
+        }
+
+        replies.push(Reply::Lineup(bout.clone()));
+        replies
+    }
+
+    /// Checks in a player for the bout of a team, identified by `id`, once
+    /// refreshed to `next_bout`. Requires `args` to be
+    /// `Some(Arguments::CheckIn(player))`.
+    fn check_in(
+        &mut self,
+        id: (usize, usize),
+        next_bout: Bout,
+        outcome: Option<Outcome>,
+        args: Option<Arguments>,
+    ) -> Vec<Reply> {
+        let (bout, mut replies) = self.refresh(id, next_bout, outcome);
+
+        if let Some(Arguments::CheckIn(player)) = args {
+            if let Err(why) = bout.check_in(player, Local::now()) {
+                replies.push(Reply::Message(why));
+            }
+        }
+
+        replies.push(Reply::Lineup(bout.clone()));
+        replies
+    }
+
+    /// Refreshes the tracked bout of a team to `next_bout`, when the bout is
+    /// polled. Replies only when something changed, or with the lineup when
+    /// the team wasn't tracked yet.
+    pub fn poll(
+        &mut self,
+        id: (usize, usize),
+        next_bout: Bout,
+        outcome: Option<Outcome>,
+    ) -> Vec<Reply> {
+        let tracked = self.bouts.contains_key(&id);
+        let (bout, mut replies) = self.refresh(id, next_bout, outcome);
+        if !tracked || !replies.is_empty() {
+            replies.push(Reply::Lineup(bout.clone()));
+        }
+        replies
+    }
+
+    /// Updates the tracked bout of a team to its next bout on spire.gg, with
+    /// the `outcome` of the tracked bout if it was played, see
+    /// `fetch_next_bout`. Returns the bout with notices of what changed since
+    /// the last refresh.
+    fn refresh(
+        &mut self,
+        id: (usize, usize),
+        mut next_bout: Bout,
+        outcome: Option<Outcome>,
+    ) -> (&mut Bout, Vec<Reply>) {
+        let mut replies = Vec::new();
+
+        match self.bouts.remove(&id) {
+            // same match, but the details may have changed on spire.gg
+            Some(previous) if previous == next_bout => {
+                let changes = next_bout.changes_since(&previous);
+                if !changes.is_empty() {
+                    let title = String::from("Match changed");
+                    let text = format!("{}\n\n{}", next_bout.get_title(), changes.join("\n"));
+                    replies.push(Reply::Message(Response::new_warning(title, text)));
+                }
+                next_bout.carry_over(previous);
+            }
+            Some(previous) => {
+                let title = String::from("Tracking a new match");
+                let text = format!(
+                    "{} is no longer the next match, now tracking {}.\n\nFinal lineup:\n{}",
+                    previous.get_title(),
+                    next_bout.get_title(),
+                    previous.get_maps()
+                );
+                replies.push(Reply::Message(Response::new_warning(title, text)));
+
+                // the previous match may have been played, if so post its result
+                let outcome = outcome.filter(|outcome| outcome.get_id() == previous.get_id());
+                if let Some(mut outcome) = outcome {
+                    outcome.set_lineup(&previous);
+                    replies.push(Reply::Outcome(outcome));
+                }
+                self.archive_bout(id, previous);
+            }
+            None => {}
+        }
+
+        let rules = self.get_rules(id);
+        next_bout.set_team_size(rules.team_size);
+        next_bout.set_checkin(rules.get_checkin_window());
+        let bout = self.bouts.entry(id).or_insert(next_bout);
+        (bout, replies)
+    }
+
+    /// Signs `player` up for the map at `index` of the tracked bout with
+    /// `bout_id`, e.g. after reacting to its lineup, unless someone else
+    /// plays it. Replies nothing if the bout isn't tracked.
+    pub fn claim(&mut self, bout_id: usize, index: usize, player: Player) -> Vec<Reply> {
+        let (id, bout) = match self
+            .bouts
+            .iter_mut()
+            .find(|(_, bout)| bout.get_id() == bout_id)
+        {
+            Some(tracked) => tracked,
+            None => return Vec::new(),
+        };
+        let rules = self.rules.get(id).cloned().unwrap_or_default();
+
+        match bout.insert_player(index, None, player, false, &rules) {
+            Ok(()) => vec![Reply::Lineup(bout.clone())],
+            Err(why) => vec![Reply::Message(why)],
+        }
+    }
+
+    /// Withdraws `player` from the map at `index` of the tracked bout with
+    /// `bout_id`. Replies nothing if the bout isn't tracked or someone else
+    /// has claimed the map since.
+    pub fn release(&mut self, bout_id: usize, index: usize, player: &Player) -> Vec<Reply> {
+        let bout = match self
+            .bouts
+            .values_mut()
+            .find(|bout| bout.get_id() == bout_id)
+        {
+            Some(bout) => bout,
+            None => return Vec::new(),
+        };
+        let slot = match bout.find_slot(index, player) {
+            Some(slot) => slot,
+            None => return Vec::new(),
+        };
+
+        match bout.remove_player(index, Some(slot)) {
+            Ok(()) => {
+                let mut replies: Vec<Reply> = promotion(bout, index).into_iter().collect();
+                replies.push(Reply::Lineup(bout.clone()));
+                replies
+            }
+            Err(why) => vec![Reply::Message(why)],
+        }
+    }
+
+    /// Shows the `outcome` of the last finished bout of a team, identified by
+    /// `id`, with the players of the stored lineup.
+    fn results(&self, id: (usize, usize), mut outcome: Outcome) -> Vec<Reply> {
+        let lineup = self
+            .archive
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .chain(self.bouts.get(&id))
+            .find(|bout| bout.get_id() == outcome.get_id());
+        if let Some(bout) = lineup {
+            outcome.set_lineup(bout);
+        }
+
+        vec![Reply::Outcome(outcome)]
+    }
+
+    /// Shows the remaining `bouts` of a team. Shows the first page, unless
+    /// `args` is `Some(Arguments::Page(page))`.
+    fn schedule(bouts: Vec<Bout>, args: Option<Arguments>) -> Vec<Reply> {
+        let page = match args {
+            Some(Arguments::Page(page)) => page,
+            _ => 1,
+        };
+
+        match Schedule::new(bouts, page) {
+            Ok(schedule) => vec![Reply::Schedule(schedule)],
+            Err(why) => vec![Reply::Message(why)],
+        }
+    }
+}
+
+/// Suggests the substitutes on the bench of `bout` once a slot of the map at
+/// `index` opened up.
+fn promotion(bout: &Bout, index: usize) -> Option<Reply> {
+    let text = bout.get_promotion(index)?;
+    let title = String::from("Substitutes available");
+    Some(Reply::Message(Response::new_warning(title, text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{self, DiscordCommands};
+    use crate::mock::{edit_fixture, read_fixture, MockSpire};
+
+    /// Runs a message through the same steps as the message handler.
+    async fn handle(
+        spire: &Spire,
+        commands: &DiscordCommands,
+        processor: &mut Processor,
+        content: &str,
+        author: &str,
+    ) -> Vec<Reply> {
+        let words = commands::get_msg_words(content);
+        let command = commands.get(words[0]).unwrap();
+        let args = commands::parse_arguments(command, &words, &player(author), None, false);
+        let args = args.unwrap();
+        process(spire, processor, command, args).await
+    }
+
+    /// Fetches and applies a command in one go, as the message handler does.
+    async fn process(
+        spire: &Spire,
+        processor: &mut Processor,
+        command: &InternalCommand,
+        args: Option<Arguments>,
+    ) -> Vec<Reply> {
+        let tracked = processor.get_tracked(command.ids());
+        let fetched = Processor::fetch(spire, command, tracked).await;
+        processor.apply(spire, command, args, fetched)
+    }
+
+    /// Polls the next bout of team 101 in tournament 42.
+    async fn poll(spire: &Spire, processor: &mut Processor) -> Vec<Reply> {
+        let id = (42, 101);
+        let tracked = processor.get_tracked(id);
+        let (next_bout, outcome) = Processor::fetch_next_bout(spire, id, tracked)
+            .await
+            .unwrap();
+        processor.poll(id, next_bout, outcome)
+    }
+
+    /// The Discord user with `name`, mentioned as `<@1>` for alice, `<@2>`
+    /// for bob and `<@3>` for carol.
+    fn player(name: &str) -> Player {
+        let id = match name {
+            "alice" => 1,
+            "bob" => 2,
+            _ => 3,
+        };
+        Player::new(id, String::from(name))
+    }
+
+    /// The maps of the lineup in the last reply.
+    fn lineup(replies: &[Reply]) -> String {
+        match replies.last() {
+            Some(Reply::Lineup(bout)) => bout.get_maps(),
+            other => panic!("expected a lineup, got {:?}", other),
+        }
+    }
+
+    async fn setup() -> (MockSpire, Spire, DiscordCommands) {
+        let server = MockSpire::start().await;
+        server.fixture("/matches?tournamentId=42&page=0", "tournament_42.json");
+        server.fixture("/matches/5001", "match_5001.json");
+        let spire = Spire::new(&server.config());
+
+        let mut commands = DiscordCommands::new();
+        for content in &[
+            "!add_command bes insert 101 42",
+            "!add_command !bes_remove remove 101 42",
+            "!add_command bes_bench bench 101 42",
+        ] {
+            let words = commands::get_msg_words(content);
+            let (name, command) = commands::parse_add_command(&words).unwrap();
+            commands.add_command(name, command);
+        }
+
+        (server, spire, commands)
+    }
+
+    #[tokio::test]
+    async fn signs_up_and_withdraws_players() {
+        let (_server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+
+        let replies = handle(&spire, &commands, &mut processor, "!bes 2", "alice").await;
+        assert_eq!(replies.len(), 1);
+        assert!(lineup(&replies).contains("<@1>: Eternal Empire LE\n"));
+
+        // players can only remove themselves
+        let replies = handle(&spire, &commands, &mut processor, "!bes_remove 2", "bob").await;
+        match &replies[0] {
+            Reply::Message(why) => assert_eq!(why.title, "Not a captain"),
+            other => panic!("expected a message, got {:?}", other),
+        }
+        assert!(lineup(&replies).contains("<@1>: Eternal Empire LE\n"));
+
+        let replies = handle(&spire, &commands, &mut processor, "!bes_remove 2", "alice").await;
+        assert!(lineup(&replies).contains("[2]: Eternal Empire LE\n"));
+    }
+
+    #[tokio::test]
+    async fn suggests_substitutes_from_the_bench() {
+        let (_server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+        handle(&spire, &commands, &mut processor, "!bes 2", "alice").await;
+
+        let replies = handle(&spire, &commands, &mut processor, "!bes_bench", "bob").await;
+        match replies.last() {
+            Some(Reply::Lineup(bout)) => assert_eq!(bout.get_bench(), "<@2>"),
+            other => panic!("expected a lineup, got {:?}", other),
+        }
+
+        let replies = handle(&spire, &commands, &mut processor, "!bes_remove 2", "alice").await;
+        match &replies[0] {
+            Reply::Message(suggestion) => {
+                assert_eq!(suggestion.title, "Substitutes available");
+                assert!(suggestion
+                    .contents
+                    .starts_with("Eternal Empire LE needs a player"));
+            }
+            other => panic!("expected a message, got {:?}", other),
+        }
+
+        // signing up for a map leaves the bench
+        let replies = handle(&spire, &commands, &mut processor, "!bes 2", "bob").await;
+        match replies.last() {
+            Some(Reply::Lineup(bout)) => assert_eq!(bout.get_bench(), "Nobody"),
+            other => panic!("expected a lineup, got {:?}", other),
+        }
+        let replies = handle(&spire, &commands, &mut processor, "!bes_bench leave", "bob").await;
+        match &replies[0] {
+            Reply::Message(why) => assert_eq!(why.title, "Not on the bench"),
+            other => panic!("expected a message, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn lets_captains_assign_and_remove_players() {
+        let (_server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+        let insert = commands.get("!bes").unwrap();
+        let remove = commands.get("!bes_remove").unwrap();
+
+        let words = ["!bes", "2", "<@42>"];
+        let denied = commands::parse_arguments(
+            insert,
+            &words,
+            &player("bob"),
+            Some(&player("alice")),
+            false,
+        );
+        assert_eq!(denied.unwrap_err().title, "Not a captain");
+
+        let args =
+            commands::parse_arguments(insert, &words, &player("bob"), Some(&player("alice")), true);
+        let replies = process(&spire, &mut processor, insert, args.unwrap()).await;
+        assert!(lineup(&replies).contains("<@1>: Eternal Empire LE\n"));
+
+        let args =
+            commands::parse_arguments(remove, &["!bes_remove", "2"], &player("bob"), None, true);
+        let replies = process(&spire, &mut processor, remove, args.unwrap()).await;
+        assert!(lineup(&replies).contains("[2]: Eternal Empire LE\n"));
+    }
+
+    #[tokio::test]
+    async fn keeps_sign_ups_when_the_maps_change() {
+        let (server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+        handle(&spire, &commands, &mut processor, "!bes 1", "alice").await;
+        handle(&spire, &commands, &mut processor, "!bes 2", "bob").await;
+
+        let changed = read_fixture("match_5001.json").replace("Death Aura LE", "Blackburn LE");
+        server.route("/matches/5001", 200, &changed);
+        let replies = handle(&spire, &commands, &mut processor, "!bes", "carol").await;
+
+        let maps = lineup(&replies);
+        assert!(maps.contains("[1]: Blackburn LE\n"));
+        assert!(maps.contains("<@2>: Eternal Empire LE\n"));
+        assert!(!maps.contains("<@1>"));
+    }
+
+    #[tokio::test]
+    async fn archives_the_lineup_of_a_finished_bout() {
+        let (server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+        handle(&spire, &commands, &mut processor, "!bes 1", "alice").await;
+
+        // the match is played, so the match a week later is the next match
+        let tournament = edit_fixture("tournament_42.json", |json| {
+            json["result"]["content"][2]["status"] = "FINISHED".into();
+        });
+        let next = edit_fixture("match_5001.json", |json| {
+            json["result"]["id"] = 5002.into();
+        });
+        server.route("/matches?tournamentId=42&page=0", 200, &tournament);
+        server.route("/matches/5002", 200, &next);
+        let replies = handle(&spire, &commands, &mut processor, "!bes", "alice").await;
+
+        match &replies[0] {
+            Reply::Message(notice) => {
+                assert_eq!(notice.title, "Tracking a new match");
+                assert!(notice.contents.contains("<@1>: Death Aura LE"));
+            }
+            other => panic!("expected a notice, got {:?}", other),
+        }
+        assert!(!lineup(&replies).contains("<@1>"));
+        assert_eq!(server.hits("/matches/5002"), 1);
+        assert_eq!(processor.archive[&(42, 101)].len(), 1);
+        assert!(processor.is_tracked(5002));
+        assert!(!processor.is_tracked(5001));
+    }
+
+    #[tokio::test]
+    async fn reports_the_result_of_a_finished_bout() {
+        let (server, spire, mut commands) = setup().await;
+        let words = commands::get_msg_words("!add_command bes_results results 101 42");
+        let (name, command) = commands::parse_add_command(&words).unwrap();
+        commands.add_command(name, command);
+        let mut processor = Processor::new();
+        handle(&spire, &commands, &mut processor, "!bes 1", "alice").await;
+
+        // the match is played, its result is posted once the next match is tracked
+        let tournament = edit_fixture("tournament_42.json", |json| {
+            json["result"]["content"][2]["status"] = "FINISHED".into();
+        });
+        let played = edit_fixture("match_5000.json", |json| {
+            json["result"]["id"] = 5001.into();
+        });
+        let next = edit_fixture("match_5001.json", |json| {
+            json["result"]["id"] = 5002.into();
+        });
+        server.route("/matches?tournamentId=42&page=0", 200, &tournament);
+        server.route("/matches/5001", 200, &played);
+        server.route("/matches/5002", 200, &next);
+        let replies = handle(&spire, &commands, &mut processor, "!bes", "alice").await;
+
+        match &replies[1] {
+            Reply::Outcome(outcome) => {
+                assert_eq!(outcome.get_winner(), Some("Beserkers"));
+                assert!(outcome.get_maps().contains("won by Beserkers (<@1>)"));
+            }
+            other => panic!("expected a result, got {:?}", other),
+        }
+
+        let replies = handle(&spire, &commands, &mut processor, "!bes_results", "bob").await;
+        match &replies[..] {
+            [Reply::Outcome(outcome)] => {
+                assert_eq!(outcome.get_id(), 5001);
+                assert!(outcome.get_maps().contains("(<@1>)"));
+            }
+            other => panic!("expected a result, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn lists_the_bouts_that_started() {
+        let (_server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+        handle(&spire, &commands, &mut processor, "!bes", "alice").await;
+
+        let start = processor.bouts[&(42, 101)].get_datetime();
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(processor
+            .started(start - chrono::Duration::minutes(1), day)
+            .is_empty());
+        assert_eq!(
+            processor.started(start + chrono::Duration::hours(2), day),
+            vec![(42, 101)]
+        );
+        assert!(processor
+            .started(start + chrono::Duration::days(2), day)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn polls_for_changes_of_the_next_bout() {
+        let (server, spire, _commands) = setup().await;
+        let mut processor = Processor::new();
+
+        let replies = poll(&spire, &mut processor).await;
+        assert!(matches!(&replies[..], [Reply::Lineup(_)]));
+        let replies = poll(&spire, &mut processor).await;
+        assert!(replies.is_empty());
+
+        let changed = edit_fixture("match_5001.json", |json| {
+            json["result"]["datetime"] = "2099-03-21T19:00:00".into();
+            json["result"]["maps"][0]["name"] = "Blackburn LE".into();
+        });
+        server.route("/matches/5001", 200, &changed);
+        let replies = poll(&spire, &mut processor).await;
+        match &replies[0] {
+            Reply::Message(notice) => {
+                assert_eq!(notice.title, "Match changed");
+                assert!(notice
+                    .contents
+                    .starts_with("Beserkers vs Team Liquid Nitrogen\n\nMoved from"));
+                assert!(notice.contents.contains("Map removed: Death Aura LE"));
+                assert!(notice.contents.contains("Map added: Blackburn LE"));
+            }
+            other => panic!("expected a notice, got {:?}", other),
+        }
+        assert!(poll(&spire, &mut processor).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn claims_and_releases_maps() {
+        let (_server, spire, commands) = setup().await;
+        let mut processor = Processor::new();
+        handle(&spire, &commands, &mut processor, "!bes", "alice").await;
+
+        let replies = processor.claim(5001, 3, player("alice"));
+        assert!(lineup(&replies).contains("<@1>: Ever Dream LE\n"));
+
+        // nor can others claim it in the meantime
+        match &processor.claim(5001, 3, player("bob"))[..] {
+            [Reply::Message(why)] => assert_eq!(why.title, "Map taken"),
+            other => panic!("expected an error, got {:?}", other),
+        }
+
+        // only the player who claimed the map can release it
+        assert!(processor.release(5001, 3, &player("bob")).is_empty());
+        let replies = processor.release(5001, 3, &player("alice"));
+        assert!(lineup(&replies).contains("[3]: Ever Dream LE\n"));
+
+        assert!(processor.claim(5002, 1, player("alice")).is_empty());
+        match &processor.claim(5001, 6, player("alice"))[..] {
+            [Reply::Message(why)] => assert_eq!(why.title, "Invalid index."),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn shows_the_schedule_of_the_team() {
+        let (_server, spire, mut commands) = setup().await;
+        let words = commands::get_msg_words("!add_command bes_schedule schedule 101 42");
+        let (name, command) = commands::parse_add_command(&words).unwrap();
+        commands.add_command(name, command);
+        let mut processor = Processor::new();
+
+        let replies = handle(&spire, &commands, &mut processor, "!bes_schedule", "alice").await;
+        match &replies[..] {
+            [Reply::Schedule(schedule)] => assert_eq!(schedule.get_bouts().len(), 2),
+            other => panic!("expected a schedule, got {:?}", other),
+        }
+
+        let replies = handle(
+            &spire,
+            &commands,
+            &mut processor,
+            "!bes_schedule 2",
+            "alice",
+        )
+        .await;
+        match &replies[..] {
+            [Reply::Message(why)] => assert_eq!(why.title, "Invalid page."),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+}
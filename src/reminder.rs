@@ -0,0 +1,162 @@
+use crate::bout::Bout;
+use chrono::prelude::*;
+use std::time::Duration;
+
+/// A notice that a bout is about to start.
+#[derive(Debug)]
+pub struct Reminder {
+    bout: Bout,
+
+    /// How long before the start the reminder is sent.
+    remaining: Duration,
+}
+
+impl Reminder {
+    /// Constructs the reminder of `bout` sent at `now`, which must be before
+    /// the start.
+    pub fn new(bout: Bout, now: DateTime<Local>) -> Reminder {
+        let remaining = bout
+            .get_datetime()
+            .signed_duration_since(now)
+            .to_std()
+            .unwrap_or_default();
+        Reminder { bout, remaining }
+    }
+
+    pub fn get_bout(&self) -> &Bout {
+        &self.bout
+    }
+
+    pub fn get_title(&self) -> String {
+        format!(
+            "{} starts in {}",
+            self.bout.get_title(),
+            format_remaining(self.remaining)
+        )
+    }
+
+    /// The players who signed up, or a notice that nobody did.
+    pub fn get_players(&self) -> String {
+        let players = self.bout.get_players();
+        if players.is_empty() {
+            return String::from("Nobody signed up yet");
+        }
+
+        let players: Vec<_> = players.iter().map(|player| player.mention()).collect();
+        players.join(", ")
+    }
+
+    /// Mentions of the players who signed up, to notify them of the
+    /// reminder. `None` if nobody did.
+    pub fn get_mentions(&self) -> Option<String> {
+        let players = self.bout.get_players();
+        if players.is_empty() {
+            return None;
+        }
+
+        let mentions: Vec<_> = players.iter().map(|player| player.mention()).collect();
+        Some(mentions.join(" "))
+    }
+
+    /// A warning if some maps have no player yet.
+    pub fn get_warning(&self) -> Option<String> {
+        if self.bout.has_open_maps() {
+            Some(String::from(
+                "Some maps still need a player, see the `[n]` entries.",
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Formats the time left to the nearest minute, e.g. `24h`, `1h 30min` or
+/// `15min`.
+fn format_remaining(remaining: Duration) -> String {
+    let mins = (remaining.as_secs() + 30) / 60;
+    match (mins / 60, mins % 60) {
+        (0, mins) => format!("{}min", mins),
+        (hours, 0) => format!("{}h", hours),
+        (hours, mins) => format!("{}h {}min", hours, mins),
+    }
+}
+
+/// Picks the reminder of `bout` that is due at `now`, given the `offsets`
+/// before the start at which reminders are sent. Only the closest offset is
+/// picked, so reminders missed while the bot was offline aren't all sent at
+/// once. Returns `None` if that reminder was already sent or the bout has
+/// started.
+pub fn due_offset(bout: &Bout, now: DateTime<Local>, offsets: &[Duration]) -> Option<Duration> {
+    let remaining = bout
+        .get_datetime()
+        .signed_duration_since(now)
+        .to_std()
+        .ok()?;
+    let due = offsets
+        .iter()
+        .filter(|offset| remaining <= **offset)
+        .min()
+        .copied()?;
+
+    match bout.get_reminded() {
+        Some(reminded) if reminded <= due => None,
+        _ => Some(due),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_each_reminder_once() {
+        let start = Utc.ymd(2099, 3, 20).and_hms(19, 0, 0);
+        let mut bout = Bout::new(
+            5001,
+            String::from("Beserker Team League"),
+            start,
+            vec![String::from("Death Aura LE")],
+            String::from("Beserkers"),
+            String::from("Team Liquid"),
+        );
+        let offsets = [
+            Duration::from_secs(24 * 60 * 60),
+            Duration::from_secs(60 * 60),
+            Duration::from_secs(15 * 60),
+        ];
+        let at = |hour, min| {
+            Utc.ymd(2099, 3, 20)
+                .and_hms(hour, min, 0)
+                .with_timezone(&Local)
+        };
+
+        assert_eq!(due_offset(&bout, at(0, 0), &offsets), Some(offsets[0]));
+        bout.set_reminded(offsets[0]);
+        assert_eq!(due_offset(&bout, at(12, 0), &offsets), None);
+
+        // the bot was offline during the 1h reminder
+        assert_eq!(due_offset(&bout, at(18, 50), &offsets), Some(offsets[2]));
+        bout.set_reminded(offsets[2]);
+        assert_eq!(due_offset(&bout, at(18, 55), &offsets), None);
+        assert_eq!(due_offset(&bout, at(19, 5), &offsets), None);
+
+        // the title tells the time actually left, not the offset
+        let reminder = Reminder::new(bout.clone(), at(18, 50));
+        assert_eq!(
+            reminder.get_title(),
+            "Beserkers vs Team Liquid starts in 10min"
+        );
+        let reminder = Reminder::new(bout.clone(), at(17, 29));
+        assert_eq!(
+            reminder.get_title(),
+            "Beserkers vs Team Liquid starts in 1h 31min"
+        );
+        let reminder = Reminder::new(bout, at(18, 0));
+        assert_eq!(
+            reminder.get_title(),
+            "Beserkers vs Team Liquid starts in 1h"
+        );
+        assert_eq!(reminder.get_players(), "Nobody signed up yet");
+        assert!(reminder.get_warning().is_some());
+    }
+}
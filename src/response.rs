@@ -1,9 +1,11 @@
+#[derive(Debug, PartialEq)]
 pub enum ResponseType {
     Error,
     Success,
     Warning,
 }
 
+#[derive(Debug)]
 pub struct Response {
     pub response_type: ResponseType,
     pub title: String,
@@ -15,7 +17,7 @@ impl Response {
         Response {
             response_type: ResponseType::Error,
             title,
-            contents
+            contents,
         }
     }
 
@@ -23,7 +25,7 @@ impl Response {
         Response {
             response_type: ResponseType::Success,
             title,
-            contents
+            contents,
         }
     }
 
@@ -31,7 +33,7 @@ impl Response {
         Response {
             response_type: ResponseType::Warning,
             title,
-            contents
+            contents,
         }
     }
 }
@@ -0,0 +1,132 @@
+use crate::response::Response;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Longest check-in window in minutes, one week.
+const MAX_CHECKIN: u64 = 7 * 24 * 60;
+
+/// Constraints on the lineup of a team, checked whenever a player signs up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Number of players per team on each map, e.g. 2 for 2v2 maps.
+    pub team_size: usize,
+
+    /// Whether a player can play only one of the maps.
+    pub one_map_per_player: bool,
+
+    /// Whether the player of the ACE map may also play one other map.
+    pub ace_exception: bool,
+
+    /// Maximum number of distinct players in the lineup.
+    pub max_players: Option<usize>,
+
+    /// Minutes before the start during which the players check in, zero if
+    /// they don't have to.
+    pub checkin: u64,
+}
+
+impl Default for Rules {
+    /// The rules of the Beserker Team League: one map per player, except for
+    /// the ACE map.
+    fn default() -> Rules {
+        Rules {
+            team_size: 1,
+            one_map_per_player: true,
+            ace_exception: true,
+            max_players: None,
+            checkin: 0,
+        }
+    }
+}
+
+impl Rules {
+    /// Changes the rule named `rule` to `value`, as given to
+    /// `!lineup_rules`.
+    pub fn set(&mut self, rule: &str, value: &str) -> Result<(), Response> {
+        match rule.to_lowercase().as_str() {
+            "team_size" => match value.parse::<usize>() {
+                Ok(size) if size > 0 => self.team_size = size,
+                _ => {
+                    let title = String::from("Invalid team size");
+                    let text = String::from("Please enter a whole positive number");
+                    return Err(Response::new_error(title, text));
+                }
+            },
+            "one_map" => self.one_map_per_player = parse_switch(value)?,
+            "ace" => self.ace_exception = parse_switch(value)?,
+            "max_players" => {
+                self.max_players = match value.parse::<usize>() {
+                    Ok(max) if max > 0 => Some(max),
+                    _ if value.eq_ignore_ascii_case("none") => None,
+                    _ => {
+                        let title = String::from("Invalid maximum");
+                        let text = String::from("Please enter a whole positive number or `none`");
+                        return Err(Response::new_error(title, text));
+                    }
+                }
+            }
+            "checkin" => match value.parse::<u64>() {
+                Ok(mins) if mins <= MAX_CHECKIN => self.checkin = mins,
+                _ if value.eq_ignore_ascii_case("off") => self.checkin = 0,
+                _ => {
+                    let title = String::from("Invalid check-in");
+                    let text = format!(
+                        "Please enter a number of minutes up to {} or `off`",
+                        MAX_CHECKIN
+                    );
+                    return Err(Response::new_error(title, text));
+                }
+            },
+            _ => {
+                let title = String::from("Unknown rule");
+                let text = format!(
+                    "`{}` is not a rule, use `team_size`, `one_map`, `ace`, `max_players` or `checkin`",
+                    rule
+                );
+                return Err(Response::new_error(title, text));
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the rules, one per line.
+    pub fn get_description(&self) -> String {
+        let switch = |on| if on { "on" } else { "off" };
+        let checkin = match self.checkin {
+            0 => String::from("off"),
+            mins => format!("{}min", mins),
+        };
+        let max_players = match self.max_players {
+            Some(max) => max.to_string(),
+            None => String::from("none"),
+        };
+        format!(
+            "team_size: {}\none_map: {}\nace: {}\nmax_players: {}\ncheckin: {}",
+            self.team_size,
+            switch(self.one_map_per_player),
+            switch(self.ace_exception),
+            max_players,
+            checkin
+        )
+    }
+
+    /// Time before the start of a bout during which the players check in.
+    pub fn get_checkin_window(&self) -> Duration {
+        // stored rules may predate the maximum
+        Duration::from_secs(60 * self.checkin.min(MAX_CHECKIN))
+    }
+}
+
+/// Parses `on` or `off`.
+fn parse_switch(value: &str) -> Result<bool, Response> {
+    match value.to_lowercase().as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => {
+            let title = String::from("Invalid value");
+            let text = String::from("Please enter `on` or `off`");
+            Err(Response::new_error(title, text))
+        }
+    }
+}
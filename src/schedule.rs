@@ -0,0 +1,104 @@
+use crate::bout::Bout;
+use crate::response::Response;
+
+/// Number of bouts shown per page of a schedule.
+pub const PAGE_SIZE: usize = 5;
+
+/// One page of the remaining bouts of a team in a tournament.
+#[derive(Debug)]
+pub struct Schedule {
+    bouts: Vec<Bout>,
+    page: usize,
+}
+
+impl Schedule {
+    /// Constructs the schedule of `bouts` showing `page`, counting from 1.
+    /// Requires `bouts` to be non-empty.
+    pub fn new(bouts: Vec<Bout>, page: usize) -> Result<Schedule, Response> {
+        let schedule = Schedule { bouts, page };
+        if page == 0 || page > schedule.pages() {
+            let text = format!("Please enter a page between 1 and {}", schedule.pages());
+            return Err(Response::new_error(String::from("Invalid page."), text));
+        }
+
+        Ok(schedule)
+    }
+
+    /// Total number of pages.
+    pub fn pages(&self) -> usize {
+        self.bouts.len().div_ceil(PAGE_SIZE)
+    }
+
+    pub fn get_title(&self) -> String {
+        format!("Schedule: {}", self.bouts[0].get_tournament())
+    }
+
+    pub fn get_description(&self) -> String {
+        let remaining = self.bouts.len();
+        if remaining == 1 {
+            String::from("1 remaining match")
+        } else {
+            format!("{} remaining matches", remaining)
+        }
+    }
+
+    /// The bouts on the current page.
+    pub fn get_bouts(&self) -> &[Bout] {
+        let start = (self.page - 1) * PAGE_SIZE;
+        let end = (start + PAGE_SIZE).min(self.bouts.len());
+        &self.bouts[start..end]
+    }
+
+    pub fn get_footer(&self) -> String {
+        if self.pages() == 1 {
+            return String::from("Page 1 of 1");
+        }
+
+        format!(
+            "Page {} of {}, add a page number to the command to see another page",
+            self.page,
+            self.pages()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+
+    fn bouts(count: usize) -> Vec<Bout> {
+        (0..count)
+            .map(|i| {
+                let datetime = Utc.ymd(2099, 3, 20).and_hms(19, 0, 0);
+                let maps = vec![String::from("Death Aura LE")];
+                let away = format!("Opponent {}", i);
+                let tournament = String::from("Beserker Team League");
+                Bout::new(
+                    i,
+                    tournament,
+                    datetime,
+                    maps,
+                    String::from("Beserkers"),
+                    away,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_the_bouts_into_pages() {
+        let schedule = Schedule::new(bouts(12), 3).unwrap();
+        assert_eq!(schedule.pages(), 3);
+        assert_eq!(schedule.get_bouts().len(), 2);
+        assert_eq!(
+            schedule.get_bouts()[0].get_title(),
+            "Beserkers vs Opponent 10"
+        );
+        assert!(schedule.get_footer().starts_with("Page 3 of 3"));
+
+        assert!(Schedule::new(bouts(12), 0).is_err());
+        assert!(Schedule::new(bouts(12), 4).is_err());
+        assert!(Schedule::new(bouts(5), 2).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::bout::MapName;
+use crate::outcome::{Outcome, Side};
+
+/// Number of finished bouts of the opponent taken into account.
+pub const RECENT_BOUTS: usize = 5;
+
+/// The recent form of an opposing team in a tournament.
+#[derive(Debug)]
+pub struct Scouting {
+    team: String,
+
+    /// Summaries of the recent bouts, latest first.
+    bouts: Vec<String>,
+
+    /// Maps with the number of times they were won and played by the team.
+    maps: Vec<(MapName, usize, usize)>,
+
+    /// Players that played for the team, in order of appearance.
+    roster: Vec<String>,
+
+    wins: usize,
+    losses: usize,
+}
+
+impl Scouting {
+    pub fn new(team: String) -> Scouting {
+        Scouting {
+            team,
+            bouts: Vec::new(),
+            maps: Vec::new(),
+            roster: Vec::new(),
+            wins: 0,
+            losses: 0,
+        }
+    }
+
+    /// Adds a finished bout in which the team played on `side`. Bouts
+    /// should be added latest first.
+    pub fn add_bout(&mut self, outcome: &Outcome, side: Side) {
+        let (home, away) = outcome.get_score();
+        let (score, opponent) = match side {
+            Side::Home => ((home, away), outcome.get_away()),
+            Side::Away => ((away, home), outcome.get_home()),
+        };
+        let result = match outcome.get_winning_side() {
+            Some(winner) if winner == side => {
+                self.wins += 1;
+                "W"
+            }
+            Some(_) => {
+                self.losses += 1;
+                "L"
+            }
+            None => "D",
+        };
+        self.bouts.push(format!(
+            "{} {}-{} vs {}",
+            result, score.0, score.1, opponent
+        ));
+
+        for (map, winner) in outcome.get_map_winners() {
+            let winner = match winner {
+                Some(winner) => *winner,
+                None => continue,
+            };
+            let i = match self.maps.iter().position(|(name, _, _)| name == map) {
+                Some(i) => i,
+                None => {
+                    self.maps.push((map.clone(), 0, 0));
+                    self.maps.len() - 1
+                }
+            };
+            if winner == side {
+                self.maps[i].1 += 1;
+            }
+            self.maps[i].2 += 1;
+        }
+    }
+
+    /// Adds a player to the roster, unless already listed.
+    pub fn add_player(&mut self, player: &str) {
+        if !self.roster.iter().any(|known| known == player) {
+            self.roster.push(String::from(player));
+        }
+    }
+
+    pub fn get_title(&self) -> String {
+        format!("Scouting: {}", &self.team)
+    }
+
+    pub fn get_description(&self) -> String {
+        if self.bouts.is_empty() {
+            return format!("{} hasn't finished a match yet", &self.team);
+        }
+        format!(
+            "{} wins, {} losses in the last {} matches",
+            self.wins,
+            self.losses,
+            self.bouts.len()
+        )
+    }
+
+    pub fn get_bouts(&self) -> String {
+        if self.bouts.is_empty() {
+            return String::from("None");
+        }
+        self.bouts.join("\n")
+    }
+
+    /// The win rate of the team per map, best map first.
+    pub fn get_maps(&self) -> String {
+        if self.maps.is_empty() {
+            return String::from("None");
+        }
+
+        let mut maps: Vec<_> = self.maps.iter().collect();
+        maps.sort_by(|a, b| (b.1 * a.2).cmp(&(a.1 * b.2)).then(b.2.cmp(&a.2)));
+        maps.iter()
+            .map(|(map, won, played)| {
+                format!("{}: {}/{} ({}%)", map, won, played, 100 * won / played)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn get_roster(&self) -> String {
+        if self.roster.is_empty() {
+            return String::from("Unknown");
+        }
+        self.roster.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_the_win_rate_per_map() {
+        let maps = |winners: [Option<Side>; 2]| {
+            vec![
+                (String::from("Death Aura LE"), winners[0]),
+                (String::from("Ever Dream LE"), winners[1]),
+            ]
+        };
+        let home = String::from("Zerg Rushers");
+        let first = Outcome::new(
+            1,
+            home.clone(),
+            String::from("Beserkers"),
+            None,
+            None,
+            maps([Some(Side::Away), Some(Side::Home)]),
+        );
+        let second = Outcome::new(
+            2,
+            String::from("Cannon Rushers"),
+            home,
+            None,
+            None,
+            maps([Some(Side::Away), None]),
+        );
+
+        let mut scouting = Scouting::new(String::from("Zerg Rushers"));
+        scouting.add_bout(&first, Side::Home);
+        scouting.add_bout(&second, Side::Away);
+
+        assert_eq!(
+            scouting.get_bouts(),
+            "D 1-1 vs Beserkers\nW 1-0 vs Cannon Rushers"
+        );
+        assert_eq!(
+            scouting.get_maps(),
+            "Ever Dream LE: 1/1 (100%)\nDeath Aura LE: 1/2 (50%)"
+        );
+        assert_eq!(
+            scouting.get_description(),
+            "1 wins, 0 losses in the last 2 matches"
+        );
+    }
+}
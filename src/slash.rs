@@ -0,0 +1,373 @@
+use crate::admin;
+use crate::bout::Player;
+use crate::commands::{self, Arguments, InternalCommand, ACTIONS};
+use crate::response::Response;
+use crate::{run_command, send_message_embed, Scope, Wrapper};
+
+use serde_json::Value;
+use serenity::http::Http;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, UserId};
+use serenity::model::interactions::application_command::{
+    ApplicationCommand, ApplicationCommandInteractionData, ApplicationCommandOptionType,
+};
+use serenity::model::interactions::{Interaction, InteractionResponseType};
+use serenity::prelude::*;
+
+/// Registers the slash commands, which mirror the prefix commands. All
+/// commands are replaced at once, as Discord limits how often commands can
+/// be created.
+pub async fn register(http: &Http) {
+    let team_commands = [
+        ("signup", "Signs you up for a map of the next match", true),
+        (
+            "withdraw",
+            "Clears the player of a map of the next match",
+            true,
+        ),
+        ("lineup", "Shows the lineup of the next match", false),
+        (
+            "bench",
+            "Joins the bench of substitutes of the next match",
+            false,
+        ),
+        ("checkin", "Confirms that you play the next match", false),
+    ];
+
+    let registered = ApplicationCommand::set_global_application_commands(http, |commands| {
+        commands.create_application_command(|c| {
+            c.name("add_command")
+                .description("Adds a command for a team in a tournament")
+                .create_option(|o| {
+                    o.name("name")
+                        .description("Name of the new command")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true)
+                })
+                .create_option(|o| {
+                    o.name("action")
+                        .description("What the command does")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true);
+                    for action in ACTIONS.iter() {
+                        o.add_string_choice(action, action);
+                    }
+                    o
+                })
+                .create_option(|o| {
+                    o.name("team_id")
+                        .description("Id of the team on spire.gg")
+                        .kind(ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+                .create_option(|o| {
+                    o.name("tournament_id")
+                        .description("Id of the tournament on spire.gg")
+                        .kind(ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+        });
+
+        commands.create_application_command(|c| {
+            c.name("remove_command")
+                .description("Removes a command")
+                .create_option(|o| {
+                    o.name("name")
+                        .description("Name of the command")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true)
+                })
+        });
+
+        for (name, description, has_map) in team_commands.iter() {
+            commands.create_application_command(|c| {
+                c.name(name).description(description).create_option(|o| {
+                    o.name("team")
+                        .description("Name of the insert command of the team")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true)
+                });
+                if *has_map {
+                    c.create_option(|o| {
+                        o.name("map")
+                            .description("Number of the map")
+                            .kind(ApplicationCommandOptionType::Integer)
+                            .required(true)
+                    });
+                    c.create_option(|o| {
+                        o.name("slot")
+                            .description("Slot of the map, for maps with several players")
+                            .kind(ApplicationCommandOptionType::Integer)
+                            .required(false)
+                    });
+                }
+                if *name == "bench" {
+                    c.create_option(|o| {
+                        o.name("leave")
+                            .description("Leave the bench instead")
+                            .kind(ApplicationCommandOptionType::Boolean)
+                            .required(false)
+                    });
+                }
+                if *name == "signup" {
+                    c.create_option(|o| {
+                        o.name("player")
+                            .description("Player to sign up instead of you, for captains")
+                            .kind(ApplicationCommandOptionType::User)
+                            .required(false)
+                    });
+                }
+                c
+            });
+        }
+        commands
+    })
+    .await;
+    if let Err(why) = registered {
+        println!("Error registering slash commands: {:?}", why);
+    }
+}
+
+/// Handles a slash command by running the same logic as the prefix
+/// commands. The replies are posted in the channel, like those of prefix
+/// commands.
+pub async fn handle(ctx: &Context, interaction: Interaction) {
+    let interaction = match interaction {
+        Interaction::ApplicationCommand(interaction) => interaction,
+        _ => return,
+    };
+    // the commands are only registered for servers
+    let (guild_id, member) = match (interaction.guild_id, &interaction.member) {
+        (Some(guild_id), Some(member)) => (guild_id, member),
+        _ => return,
+    };
+    let options = &interaction.data;
+
+    // acknowledge right away, as the command may need to wait for spire.gg
+    let acknowledged = interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+        })
+        .await;
+    if let Err(why) = acknowledged {
+        println!("Error acknowledging slash command: {:?}", why);
+        return;
+    }
+
+    let scope = Scope::Guild(guild_id);
+    let channel = interaction.channel_id;
+
+    let manages_commands = matches!(options.name.as_ref(), "add_command" | "remove_command");
+    let response = if manages_commands
+        && !admin::is_admin(ctx, guild_id, member.user.id, &member.roles).await
+    {
+        Some(admin::denied())
+    } else {
+        match options.name.as_ref() {
+            "add_command" => add_command(ctx, scope, channel, options).await,
+            "remove_command" => remove_command(ctx, scope, options).await,
+            name => match team_command(ctx, scope, name, options, member).await {
+                Ok((command, args)) => {
+                    run_command(ctx, scope, channel, &command, args).await;
+                    None
+                }
+                Err(why) => Some(why),
+            },
+        }
+    };
+    if let Some(response) = response {
+        if let Err(why) = send_message_embed(response, channel, &ctx.http).await {
+            println!("Error sending message: {:?}", why);
+        }
+    }
+
+    // the replies have been posted, so the acknowledgement can go
+    let deleted = interaction
+        .delete_original_interaction_response(&ctx.http)
+        .await;
+    if let Err(why) = deleted {
+        println!("Error removing the acknowledgement: {:?}", why);
+    }
+}
+
+/// Handles `/add_command`, see `!add_command`.
+async fn add_command(
+    ctx: &Context,
+    scope: Scope,
+    channel: ChannelId,
+    options: &ApplicationCommandInteractionData,
+) -> Option<Response> {
+    let (name, command) = match parse_add_command(options) {
+        Ok(parsed) => parsed,
+        Err(why) => return Some(why),
+    };
+
+    let mut data = ctx.data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let response = wrapper
+        .community_mut(scope)
+        .add_command(name, command, channel);
+    wrapper.save();
+    Some(response)
+}
+
+/// Parses the options of `/add_command` into the new (prefixed) command and
+/// its action.
+fn parse_add_command(
+    options: &ApplicationCommandInteractionData,
+) -> Result<(String, InternalCommand), Response> {
+    let name = commands::prefixed(string_option(options, "name")?);
+    let command = commands::parse_action(string_option(options, "action")?)?;
+    let team_id = integer_option(options, "team_id")?;
+    let tournament_id = integer_option(options, "tournament_id")?;
+    Ok((name, command(tournament_id, team_id)))
+}
+
+/// Handles `/remove_command`, see `!remove_command`.
+async fn remove_command(
+    ctx: &Context,
+    scope: Scope,
+    options: &ApplicationCommandInteractionData,
+) -> Option<Response> {
+    let name = match string_option(options, "name") {
+        Ok(name) => commands::prefixed(name),
+        Err(why) => return Some(why),
+    };
+
+    let mut data = ctx.data.write().await;
+    let wrapper = data.get_mut::<Wrapper>().unwrap();
+    let response = wrapper.community_mut(scope).remove_command(&name);
+    wrapper.save();
+    Some(response)
+}
+
+/// Translates `/signup`, `/withdraw`, `/lineup`, `/bench` and `/checkin` into
+/// the command and arguments of the team, which is identified by one of its
+/// commands.
+/// Captains of the team may sign up or withdraw other players.
+async fn team_command(
+    ctx: &Context,
+    scope: Scope,
+    name: &str,
+    options: &ApplicationCommandInteractionData,
+    member: &Member,
+) -> Result<(InternalCommand, Option<Arguments>), Response> {
+    let team = commands::prefixed(string_option(options, "team")?);
+    let data = ctx.data.read().await;
+    let community = data.get::<Wrapper>().unwrap().communities.get(&scope);
+    let command = community.and_then(|community| community.commands.get(&team));
+    let (tournament_id, team_id) = match command {
+        Some(command) => command.ids(),
+        None => {
+            let text = format!("The command `{}` could not be found.", &team);
+            return Err(Response::new_warning(
+                String::from("Command not found"),
+                text,
+            ));
+        }
+    };
+    let captain = community
+        .map(|community| community.is_captain((tournament_id, team_id), &member.roles))
+        .unwrap_or(false);
+    drop(data);
+
+    let player = Player::new(member.user.id.0, member.display_name().into_owned());
+    match name {
+        "signup" => {
+            let index = integer_option(options, "map")?;
+            let assignee = match string_option(options, "player") {
+                Ok(id) => get_player(ctx, id).await?,
+                Err(_) => player.clone(),
+            };
+            if assignee != player && !captain {
+                return Err(commands::not_captain());
+            }
+            let command = InternalCommand::Insert(tournament_id, team_id);
+            let slot = integer_option(options, "slot").ok();
+            Ok((
+                command,
+                Some(Arguments::Insert(assignee, index, slot, captain)),
+            ))
+        }
+        "withdraw" => {
+            let index = integer_option(options, "map")?;
+            let player = if captain { None } else { Some(player) };
+            let command = InternalCommand::Remove(tournament_id, team_id);
+            let slot = integer_option(options, "slot").ok();
+            Ok((command, Some(Arguments::Remove(index, slot, player))))
+        }
+        "bench" => {
+            let leaves = option(options, "leave")
+                .ok()
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let command = InternalCommand::Bench(tournament_id, team_id);
+            Ok((command, Some(Arguments::Bench(player, !leaves))))
+        }
+        "checkin" => {
+            let command = InternalCommand::CheckIn(tournament_id, team_id);
+            Ok((command, Some(Arguments::CheckIn(player))))
+        }
+        _ => Ok((InternalCommand::Insert(tournament_id, team_id), None)),
+    }
+}
+
+/// Looks up the user with the id given as a `User` option.
+async fn get_player(ctx: &Context, id: &str) -> Result<Player, Response> {
+    let user = match id.parse::<u64>() {
+        Ok(id) => UserId(id).to_user(ctx).await.ok(),
+        Err(_) => None,
+    };
+    match user {
+        Some(user) => Ok(Player::new(user.id.0, user.name)),
+        None => {
+            let text = String::from("The player could not be found.");
+            Err(Response::new_error(String::from("Unknown player"), text))
+        }
+    }
+}
+
+/// Gets the value of the option `name`.
+fn option<'a>(
+    options: &'a ApplicationCommandInteractionData,
+    name: &str,
+) -> Result<&'a Value, Response> {
+    let value = options
+        .options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref());
+    match value {
+        Some(value) => Ok(value),
+        None => {
+            let text = format!("Please provide `{}`", name);
+            Err(Response::new_error(String::from("Missing option"), text))
+        }
+    }
+}
+
+fn string_option<'a>(
+    options: &'a ApplicationCommandInteractionData,
+    name: &str,
+) -> Result<&'a str, Response> {
+    match option(options, name)?.as_str() {
+        Some(value) => Ok(value),
+        None => {
+            let text = format!("`{}` should be text", name);
+            Err(Response::new_error(String::from("Invalid option"), text))
+        }
+    }
+}
+
+fn integer_option(
+    options: &ApplicationCommandInteractionData,
+    name: &str,
+) -> Result<usize, Response> {
+    match option(options, name)?.as_u64() {
+        Some(value) => Ok(value as usize),
+        None => {
+            let title = String::from("Please enter a whole positive number");
+            Err(Response::new_error(title, format!("Invalid `{}`", name)))
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::outcome::{Outcome, Side};
+
+/// The record of one team in a tournament.
+#[derive(Debug)]
+pub struct Row {
+    pub team_id: usize,
+    pub name: String,
+    pub wins: usize,
+    pub losses: usize,
+    pub maps_won: usize,
+    pub maps_lost: usize,
+}
+
+impl Row {
+    fn new(team_id: usize, name: &str) -> Row {
+        Row {
+            team_id,
+            name: String::from(name),
+            wins: 0,
+            losses: 0,
+            maps_won: 0,
+            maps_lost: 0,
+        }
+    }
+
+    /// Maps won minus maps lost.
+    pub fn map_differential(&self) -> isize {
+        self.maps_won as isize - self.maps_lost as isize
+    }
+}
+
+/// The standings of a tournament, or of one of its groups or brackets,
+/// highlighting the team with `team_id`.
+#[derive(Debug)]
+pub struct Standings {
+    tournament: String,
+
+    /// The name of the group or bracket, `None` for the whole tournament.
+    stage: Option<String>,
+    team_id: usize,
+    rows: Vec<Row>,
+}
+
+impl Standings {
+    pub fn new(tournament: String, stage: Option<String>, team_id: usize) -> Standings {
+        Standings {
+            tournament,
+            stage,
+            team_id,
+            rows: Vec::new(),
+        }
+    }
+
+    fn row_mut(&mut self, team_id: usize, name: &str) -> &mut Row {
+        match self.rows.iter().position(|row| row.team_id == team_id) {
+            Some(i) => &mut self.rows[i],
+            None => {
+                self.rows.push(Row::new(team_id, name));
+                self.rows.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Adds a team to the standings, so teams that haven't played yet are
+    /// listed as well.
+    pub fn add_team(&mut self, team_id: usize, name: &str) {
+        self.row_mut(team_id, name);
+    }
+
+    /// Records the result of a finished bout between the teams with
+    /// `home_id` and `away_id`. A bout without a winner only counts its maps.
+    pub fn record(&mut self, home_id: usize, away_id: usize, outcome: &Outcome) {
+        let (home_maps, away_maps) = outcome.get_score();
+        let winner = outcome.get_winning_side();
+
+        let home = self.row_mut(home_id, outcome.get_home());
+        home.maps_won += home_maps;
+        home.maps_lost += away_maps;
+        match winner {
+            Some(Side::Home) => home.wins += 1,
+            Some(Side::Away) => home.losses += 1,
+            None => {}
+        }
+
+        let away = self.row_mut(away_id, outcome.get_away());
+        away.maps_won += away_maps;
+        away.maps_lost += home_maps;
+        match winner {
+            Some(Side::Away) => away.wins += 1,
+            Some(Side::Home) => away.losses += 1,
+            None => {}
+        }
+    }
+
+    /// The teams ranked by wins, then map differential, then name.
+    pub fn get_rows(&self) -> Vec<&Row> {
+        let mut rows: Vec<_> = self.rows.iter().collect();
+        rows.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then(b.map_differential().cmp(&a.map_differential()))
+                .then(a.name.cmp(&b.name))
+        });
+        rows
+    }
+
+    /// The position of the team, counting from 1.
+    pub fn get_position(&self) -> Option<usize> {
+        self.get_rows()
+            .iter()
+            .position(|row| row.team_id == self.team_id)
+            .map(|i| i + 1)
+    }
+
+    pub fn get_title(&self) -> String {
+        match &self.stage {
+            Some(stage) => format!("Standings: {}, {}", &self.tournament, stage),
+            None => format!("Standings: {}", &self.tournament),
+        }
+    }
+
+    pub fn get_description(&self) -> String {
+        let position = match self.get_position() {
+            Some(position) => position,
+            None => return String::from("The team hasn't played in this tournament"),
+        };
+        let name = &self.get_rows()[position - 1].name;
+        match &self.stage {
+            Some(stage) => format!(
+                "{} is #{} of {} in {}",
+                name,
+                position,
+                self.rows.len(),
+                stage
+            ),
+            None => format!("{} is #{} of {}", name, position, self.rows.len()),
+        }
+    }
+
+    /// The standings as a table, the row of the team marked with `>`.
+    pub fn get_table(&self) -> String {
+        let mut result = String::from("```\n  #  Team                  W  L  Maps\n");
+        for (i, row) in self.get_rows().iter().enumerate() {
+            let marker = if row.team_id == self.team_id {
+                '>'
+            } else {
+                ' '
+            };
+            let name: String = row.name.chars().take(20).collect();
+            result.push_str(&format!(
+                "{}{:>2}  {:<20} {:>2} {:>2}  {:+}\n",
+                marker,
+                i + 1,
+                name,
+                row.wins,
+                row.losses,
+                row.map_differential()
+            ));
+        }
+        result.push_str("```");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(home: &str, away: &str, score: (usize, usize)) -> Outcome {
+        let (home, away) = (String::from(home), String::from(away));
+        Outcome::new(1, home, away, None, Some(score), Vec::new())
+    }
+
+    #[test]
+    fn ranks_teams_by_wins_and_map_differential() {
+        let stage = Some(String::from("Group A"));
+        let mut standings = Standings::new(String::from("Beserker Team League"), stage, 101);
+        standings.add_team(404, "Cannon Rushers");
+        standings.record(101, 202, &outcome("Beserkers", "Team Liquid", (3, 2)));
+        standings.record(303, 404, &outcome("Zerg Rushers", "Cannon Rushers", (4, 0)));
+        standings.record(202, 303, &outcome("Team Liquid", "Zerg Rushers", (2, 2)));
+
+        let names: Vec<_> = standings.get_rows().iter().map(|row| &row.name).collect();
+        assert_eq!(
+            names,
+            ["Zerg Rushers", "Beserkers", "Team Liquid", "Cannon Rushers"]
+        );
+        assert_eq!(standings.get_position(), Some(2));
+        assert_eq!(
+            standings.get_description(),
+            "Beserkers is #2 of 4 in Group A"
+        );
+        assert!(standings
+            .get_table()
+            .contains("> 2  Beserkers             1  0  +1\n"));
+    }
+}
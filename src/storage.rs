@@ -0,0 +1,65 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// JSON file in which the bot state is kept between restarts.
+pub struct Storage {
+    path: PathBuf,
+}
+
+impl Storage {
+    /// Constructs a new storage backed by the file at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Storage {
+        Storage { path: path.into() }
+    }
+
+    /// Reads the stored state. Returns `Ok(None)` if nothing has been saved
+    /// yet.
+    pub fn load<T: DeserializeOwned>(&self) -> io::Result<Option<T>> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(why) => return Err(why),
+        };
+
+        let value = serde_json::from_str(&data)?;
+        Ok(Some(value))
+    }
+
+    /// Writes `value` to the storage file. The data is first written to a
+    /// temporary file, so a crash halfway never leaves a corrupt file behind.
+    pub fn save<T: Serialize>(&self, value: &T) -> io::Result<()> {
+        let data = serde_json::to_string_pretty(value)?;
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, data)?;
+        fs::rename(&temporary, &self.path)
+    }
+}
+
+/// (De)serializes a `HashMap` as a list of key-value pairs, for maps whose
+/// keys cannot be represented as JSON strings.
+pub mod pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}